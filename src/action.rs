@@ -1,8 +1,9 @@
 use std::{
     collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
     io::Write,
-    path::PathBuf,
-    process::{Command, Stdio},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
     sync::{OnceLock, mpsc},
     thread,
     time::{Duration, Instant},
@@ -12,9 +13,13 @@ use anyhow::{Context, Result};
 use async_lsp::lsp_types::{CodeAction, CodeActionKind, CodeActionParams};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
+    action_inner::{Reformat, Rename},
+    audit::{self, AuditEntry},
     loader::{Dirs, config_dir},
+    markdown::Footnote,
     parser::{Parser, StrOrSeq, parse},
     variables::{VariableInit, Variables},
 };
@@ -23,12 +28,52 @@ use crate::{
 pub struct Action {
     /// 捕捉, 支持单行或者两行
     title: String,
-    /// 返回: shell bool
+    /// 返回: shell bool，为空时始终启用
+    #[serde(default)]
     filter: StrOrSeq,
     /// shell 执行 返回 string
+    #[serde(default)]
     shell: StrOrSeq, // string
+    /// 内嵌脚本，设置后代替 shell 执行，需要开启 `script` feature
+    script: Option<StrOrSeq>,
     /// 简介
     description: Option<StrOrSeq>,
+    /// filter 结果缓存策略
+    #[serde(rename = "filterCache", default)]
+    filter_cache: FilterCache,
+}
+
+/// filter 结果缓存时长
+const FILTER_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// filter 结果缓存策略
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterCache {
+    /// 按 filter 实际引用的内容缓存（展开后的脚本与选中文本）
+    #[default]
+    Auto,
+    /// 同一文件内共享结果
+    File,
+    /// 同一行内共享结果
+    Line,
+    /// 不缓存，每次都执行
+    None,
+}
+
+impl FilterCache {
+    /// 计算缓存键，`None` 表示不缓存
+    fn key(&self, lang: &str, name: &str, script: &str, init: &VariableInit) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        (lang, name).hash(&mut hasher);
+        match self {
+            FilterCache::Auto => (script, &init.selected_text).hash(&mut hasher),
+            FilterCache::File => init.file_path.hash(&mut hasher),
+            FilterCache::Line => (&init.file_path, init.line_pos).hash(&mut hasher),
+            FilterCache::None => return None,
+        }
+        Some(hasher.finish())
+    }
 }
 
 impl Action {
@@ -38,9 +83,6 @@ impl Action {
         variable_init: &VariableInit,
         data: &ActionData,
     ) -> Option<(CodeAction, ActionData)> {
-        let shell = self.shell.to_string();
-        let shell = Variables::replace_all(&shell, variable_init);
-
         // 让其返回到 action/re
         let action = CodeAction {
             title: self.title.clone(),
@@ -50,6 +92,20 @@ impl Action {
         };
         // data: Some(serde_json::to_value(data.with_command(shell).clone()).unwrap()),
 
+        if let Some(script) = &self.script {
+            if cfg!(feature = "script") {
+                return Some((action, data.with_script(script.to_string())));
+            }
+            warn!(
+                "{}: script action requires the `script` feature",
+                self.title
+            );
+            return None;
+        }
+
+        let shell = self.shell.to_string();
+        let shell = Variables::replace_all(&shell, variable_init);
+
         Some((action, data.with_command(shell)))
     }
 
@@ -67,6 +123,10 @@ impl Action {
 pub struct ActionData {
     pub params: CodeActionParams,
     pub command: Option<String>,
+    pub script: Option<String>,
+    pub reformat: Option<Reformat>,
+    pub rename: Option<Rename>,
+    pub footnote: Option<Footnote>,
 }
 
 impl ActionData {
@@ -76,6 +136,34 @@ impl ActionData {
             ..self.clone()
         }
     }
+
+    pub fn with_script(&self, script: String) -> Self {
+        ActionData {
+            script: Some(script),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_reformat(&self, reformat: Reformat) -> Self {
+        ActionData {
+            reformat: Some(reformat),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_rename(&self, rename: Rename) -> Self {
+        ActionData {
+            rename: Some(rename),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_footnote(&self, footnote: Footnote) -> Self {
+        ActionData {
+            footnote: Some(footnote),
+            ..self.clone()
+        }
+    }
 }
 
 impl From<CodeActionParams> for ActionData {
@@ -83,18 +171,50 @@ impl From<CodeActionParams> for ActionData {
         ActionData {
             params: value.clone(),
             command: None,
+            script: None,
+            reformat: None,
+            rename: None,
+            footnote: None,
         }
     }
 }
 
-fn actions_list() -> &'static Mutex<HashMap<String, Actions>> {
-    static ACTIONS: OnceLock<Mutex<HashMap<String, Actions>>> = OnceLock::new();
+/// 以 (语言, 是否包含项目 actions) 为键缓存
+fn actions_list() -> &'static Mutex<HashMap<(String, bool), Actions>> {
+    static ACTIONS: OnceLock<Mutex<HashMap<(String, bool), Actions>>> = OnceLock::new();
     ACTIONS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
 pub(crate) fn actions_list_clear() {
     let mut actions_list = actions_list().lock();
     actions_list.clear();
+    filter_cache().lock().clear();
+}
+
+fn filter_cache() -> &'static Mutex<HashMap<u64, (Instant, bool)>> {
+    static FILTER_CACHE: OnceLock<Mutex<HashMap<u64, (Instant, bool)>>> = OnceLock::new();
+    FILTER_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 读取 filter 缓存，未命中或过期时执行并写入
+fn cached_filter(key: Option<u64>, run: impl FnOnce() -> bool) -> bool {
+    let Some(key) = key else {
+        return run();
+    };
+
+    if let Some((at, enabled)) = filter_cache().lock().get(&key)
+        && at.elapsed() < FILTER_CACHE_TTL
+    {
+        return *enabled;
+    }
+
+    let enabled = run();
+
+    let mut cache = filter_cache().lock();
+    cache.retain(|_, (at, _)| at.elapsed() < FILTER_CACHE_TTL);
+    cache.insert(key, (Instant::now(), enabled));
+
+    enabled
 }
 
 /// 语言包
@@ -127,26 +247,32 @@ impl Actions {
         Actions { name, actions }
     }
 
-    pub fn get_lang(lang_name: String, init: &VariableInit) -> Actions {
+    /// 获取语言 actions，`project` 为 false 时忽略项目目录下的 actions
+    pub fn get_lang(lang_name: String, init: &VariableInit, project: bool) -> Actions {
         let mut actions_list = actions_list().lock();
+        let key = (lang_name.clone(), project);
 
-        let mut actions = match actions_list.get(&lang_name) {
+        let mut actions = match actions_list.get(&key) {
             Some(has) => has.clone(),
             None => {
                 let file_name = format!("{}.json", lang_name.clone().to_lowercase());
+                let project_file = init
+                    .work_path
+                    .join(".helix")
+                    .join(Dirs::Actions.to_string())
+                    .join(&file_name);
                 let lang_actions = from_files(
-                    lang_name.clone(),
+                    lang_name,
                     [
-                        init.work_path
-                            .join(".helix")
-                            .join(Dirs::Actions.to_string())
-                            .join(&file_name),
-                        config_dir(Dirs::Actions).join(&file_name),
+                        project.then_some(project_file),
+                        Some(config_dir(Dirs::Actions).join(&file_name)),
                     ]
-                    .to_vec(),
+                    .into_iter()
+                    .flatten()
+                    .collect(),
                 );
 
-                actions_list.insert(lang_name, lang_actions.clone());
+                actions_list.insert(key, lang_actions.clone());
                 lang_actions
             }
         };
@@ -184,10 +310,20 @@ impl Actions {
                 let shell_script = action.filter.to_string();
                 let shell_script = Variables::replace_all(&shell_script, init);
 
-                let filter = match shell(&shell_script, &Some(init.selected_text.clone())) {
-                    Ok(s) => matches!(s.to_lowercase().as_str(), "true" | "1"),
-                    Err(_) => false,
-                };
+                let key = action
+                    .filter_cache
+                    .key(&self.name, &name, &shell_script, init);
+                let filter = cached_filter(key, || {
+                    match shell_logged(
+                        &name,
+                        &init.file_path,
+                        &shell_script,
+                        &Some(init.selected_text.clone()),
+                    ) {
+                        Ok(s) => matches!(s.to_lowercase().as_str(), "true" | "1"),
+                        Err(_) => false,
+                    }
+                });
                 match filter {
                     true => Some((name, action)),
                     false => None,
@@ -214,8 +350,37 @@ fn from_files(name: String, files: Vec<PathBuf>) -> Actions {
         )
 }
 
+/// 执行 action 命令，开启审计日志时记录执行情况
+pub fn shell_logged(
+    action: &str,
+    file: &Path,
+    cmd: &str,
+    input: &Option<String>,
+) -> Result<String> {
+    if !audit::enabled() {
+        return shell(cmd, input);
+    }
+
+    let start_time = Instant::now();
+    let output = execute(cmd, input);
+    audit::record(&AuditEntry::new(
+        action,
+        file,
+        cmd,
+        start_time.elapsed(),
+        &output,
+    ));
+
+    output_to_string(output?)
+}
+
 /// 异步核心实现（保持原有逻辑）
 pub fn shell(cmd: &str, input: &Option<String>) -> Result<String> {
+    output_to_string(execute(cmd, input)?)
+}
+
+/// 启动子进程并带超时等待输出
+fn execute(cmd: &str, input: &Option<String>) -> Result<Output> {
     let shell = get_shell();
     let mut process = Command::new(&shell[0]);
     process
@@ -259,9 +424,9 @@ pub fn shell(cmd: &str, input: &Option<String>) -> Result<String> {
     });
 
     // 带超时等待
-    let output = match rx.recv_timeout(timeout) {
-        Ok(Ok(output)) => output,
-        Ok(Err(e)) => return Err(e).context("Child process error"),
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(e)) => Err(e).context("Child process error"),
         Err(_) => {
             let elapsed = start_time.elapsed().as_secs();
             anyhow::bail!(
@@ -270,8 +435,10 @@ pub fn shell(cmd: &str, input: &Option<String>) -> Result<String> {
                 timeout.as_secs()
             )
         }
-    };
+    }
+}
 
+fn output_to_string(output: Output) -> Result<String> {
     // 错误状态处理
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -301,9 +468,62 @@ fn get_shell() -> Vec<String> {
 
 #[cfg(test)]
 mod test {
-    use super::shell;
+    use std::{cell::Cell, path::PathBuf};
+
+    use super::{Action, FilterCache, cached_filter, shell};
+    use crate::variables::VariableInit;
     use anyhow::Result;
 
+    #[test]
+    fn test_filter_cache_key() {
+        let mut init = VariableInit {
+            file_path: PathBuf::from("/tmp/a.go"),
+            line_pos: 1,
+            ..Default::default()
+        };
+        let auto = FilterCache::Auto.key("go", "run", "echo 1", &init);
+        let file = FilterCache::File.key("go", "run", "echo 1", &init);
+        let line = FilterCache::Line.key("go", "run", "echo 1", &init);
+
+        assert!(
+            FilterCache::None
+                .key("go", "run", "echo 1", &init)
+                .is_none()
+        );
+        assert_ne!(auto, FilterCache::Auto.key("go", "run", "echo 2", &init));
+
+        init.line_pos = 2;
+        assert_eq!(file, FilterCache::File.key("go", "run", "echo 1", &init));
+        assert_ne!(line, FilterCache::Line.key("go", "run", "echo 1", &init));
+        assert_ne!(file, FilterCache::File.key("go", "test", "echo 1", &init));
+    }
+
+    #[test]
+    fn test_cached_filter() {
+        let runs = Cell::new(0);
+        let run = || {
+            runs.set(runs.get() + 1);
+            true
+        };
+
+        assert!(cached_filter(Some(u64::MAX), run));
+        assert!(cached_filter(Some(u64::MAX), run));
+        assert_eq!(runs.get(), 1);
+
+        assert!(cached_filter(None, run));
+        assert!(cached_filter(None, run));
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn test_script_action_without_filter() {
+        let action: Action =
+            serde_json::from_str(r#"{ "title": "Upper", "script": "selection.to_upper()" }"#)
+                .unwrap();
+        assert!(action.filter.to_string().is_empty());
+        assert!(action.shell.to_string().is_empty());
+    }
+
     // #[test]
     #[allow(dead_code)]
     fn test_basic_command() -> Result<()> {
@@ -1,56 +1,439 @@
 use std::collections::HashMap;
 
 use async_lsp::lsp_types::{
-    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, TextEdit, WorkspaceEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Position, Range, TextEdit,
+    Url, WorkspaceEdit,
 };
-use convert_case::{Case, Casing};
-use ropey::RopeSlice;
+use convert_case::{Boundary, Case, Converter, pattern};
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
 
-pub(super) fn case_actions(
-    range_content: RopeSlice,
-    params: &CodeActionParams,
-) -> Vec<CodeActionOrCommand> {
-    if params.range.start.line != params.range.end.line {
-        return Vec::new();
-    }
+use crate::encoding::{char_is_word, find_word_ranges, get_current_word_range, get_range_content};
+
+mod codec;
+mod comment;
+mod data;
+mod lines;
+mod number;
+mod quote;
+
+pub(super) use codec::codec_actions;
+pub(crate) use codec::html_unescape;
+pub(super) use comment::comment_actions;
+pub(crate) use data::{Reformat, data_actions};
+pub(super) use lines::line_actions;
+pub(crate) use lines::natural_cmp;
+pub(super) use number::number_actions;
+pub(super) use quote::quote_actions;
+
+/// 拆分首尾空白，返回 (首部空白, 内容, 尾部空白)
+fn trim_parts(text: &str) -> (&str, &str, &str) {
+    let content = text.trim();
+    let start = text.len() - text.trim_start().len();
+    (&text[..start], content, &text[start + content.len()..])
+}
+
+/// 替换指定区域的内置 action
+fn code_action(
+    title: String,
+    kind: CodeActionKind,
+    uri: &Url,
+    range: Range,
+    new_text: String,
+) -> CodeActionOrCommand {
+    code_action_edits(title, kind, uri, vec![TextEdit { range, new_text }])
+}
 
-    let range_content = range_content.to_string();
+/// 包含多处编辑的内置 action
+fn code_action_edits(
+    title: String,
+    kind: CodeActionKind,
+    uri: &Url,
+    edits: Vec<TextEdit>,
+) -> CodeActionOrCommand {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), edits);
 
-    if range_content.len() == 1 || !range_content.is_ascii() {
-        return Vec::new();
+    CodeAction {
+        title,
+        kind: Some(kind),
+        edit: Some(WorkspaceEdit::new(changes)),
+        ..Default::default()
     }
+    .into()
+}
 
-    let items = [
-        ("case_snake", Case::Snake),
-        ("CasePascal", Case::Pascal),
-        ("caseCamel", Case::Camel),
-    ];
+/// 测试用的代码操作参数
+#[cfg(test)]
+fn test_params(range: Range) -> CodeActionParams {
+    CodeActionParams {
+        text_document: async_lsp::lsp_types::TextDocumentIdentifier::new(
+            Url::parse("file:///a.txt").unwrap(),
+        ),
+        range,
+        context: Default::default(),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    }
+}
 
-    items
-        .iter()
-        .filter_map(|&(item, case)| {
-            let out = range_content.to_case(case);
-            if out.eq(&range_content) {
-                return None;
+/// 测试用，返回各 action 的标题与第一处编辑的文本
+#[cfg(test)]
+fn action_outputs(actions: Vec<CodeActionOrCommand>) -> Vec<(String, String)> {
+    actions
+        .into_iter()
+        .filter_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => {
+                let edits = action.edit?.changes?.into_values().next()?;
+                Some((action.title, edits[0].new_text.clone()))
             }
+            _ => None,
+        })
+        .collect()
+}
+
+/// 单词风格
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseStyle {
+    Snake,
+    Pascal,
+    Camel,
+    Kebab,
+    #[serde(alias = "screaming_snake")]
+    Constant,
+    Title,
+    Sentence,
+    Dot,
+    Path,
+    Train,
+}
+
+impl CaseStyle {
+    pub fn all() -> Vec<CaseStyle> {
+        use CaseStyle::*;
+        vec![
+            Snake, Pascal, Camel, Kebab, Constant, Title, Sentence, Dot, Path, Train,
+        ]
+    }
+
+    /// action 标题，使用对应风格书写
+    fn title(&self) -> &'static str {
+        match self {
+            CaseStyle::Snake => "case_snake",
+            CaseStyle::Pascal => "CasePascal",
+            CaseStyle::Camel => "caseCamel",
+            CaseStyle::Kebab => "case-kebab",
+            CaseStyle::Constant => "CASE_CONSTANT",
+            CaseStyle::Title => "Case Title",
+            CaseStyle::Sentence => "Case sentence",
+            CaseStyle::Dot => "case.dot",
+            CaseStyle::Path => "case/path",
+            CaseStyle::Train => "Case-Train",
+        }
+    }
+
+    fn convert(&self, text: &str) -> String {
+        // 额外识别 dot.case 与 path/case 的分隔符
+        let converter = Converter::new()
+            .add_boundaries(&[Boundary::from_delim("."), Boundary::from_delim("/")]);
+
+        let converter = match self {
+            CaseStyle::Snake => converter.to_case(Case::Snake),
+            CaseStyle::Pascal => converter.to_case(Case::Pascal),
+            CaseStyle::Camel => converter.to_case(Case::Camel),
+            CaseStyle::Kebab => converter.to_case(Case::Kebab),
+            CaseStyle::Constant => converter.to_case(Case::Constant),
+            CaseStyle::Title => converter.to_case(Case::Title),
+            CaseStyle::Sentence => converter.to_case(Case::Sentence),
+            CaseStyle::Train => converter.to_case(Case::Train),
+            CaseStyle::Dot => converter.set_pattern(pattern::lowercase).set_delim("."),
+            CaseStyle::Path => converter.set_pattern(pattern::lowercase).set_delim("/"),
+        };
+
+        converter.convert(text)
+    }
+}
+
+/// 需要转换风格的文本及其区域
+struct CaseTarget {
+    range: Range,
+    text: String,
+}
+
+impl CaseTarget {
+    /// 有选择区域时使用选择内容，否则使用光标所在单词
+    fn new(doc: &Rope, range: Range) -> Option<CaseTarget> {
+        let content = get_range_content(doc, &range)?;
+
+        // 单字符选择视为光标
+        if range.start.line != range.end.line
+            || (range.start != range.end && content.len_chars() > 1)
+        {
+            return Some(CaseTarget {
+                range,
+                text: content.to_string(),
+            });
+        }
+
+        let line = doc.get_line(range.start.line as usize)?;
+        let cursor =
+            line.utf16_cu_to_char((range.start.character as usize).min(line.len_utf16_cu()));
+        let (start, end) = get_current_word_range(&line, cursor)?;
+        Some(CaseTarget {
+            range: Range::new(
+                Position::new(range.start.line, line.char_to_utf16_cu(start) as u32),
+                Position::new(range.start.line, line.char_to_utf16_cu(end) as u32),
+            ),
+            text: line.slice(start..end).to_string(),
+        })
+    }
+
+    /// 逐行转换，保留每行首尾空白
+    fn convert(&self, style: CaseStyle) -> String {
+        self.text
+            .split('\n')
+            .map(|line| {
+                let content = line.trim();
+                if content.is_empty() {
+                    return line.to_string();
+                }
+                let start = line.len() - line.trim_start().len();
+                let end = start + content.len();
+                format!(
+                    "{}{}{}",
+                    &line[..start],
+                    style.convert(content),
+                    &line[end..]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
 
-            let mut changes = HashMap::new();
-            let edits = vec![TextEdit {
-                range: params.range,
-                new_text: out,
-            }];
-            changes.insert(params.text_document.uri.clone(), edits);
+/// 单词风格转换
+pub(super) fn case_actions(
+    doc: &Rope,
+    params: &CodeActionParams,
+    styles: &[CaseStyle],
+) -> Vec<CodeActionOrCommand> {
+    let target = match CaseTarget::new(doc, params.range) {
+        Some(target) => target,
+        None => return Vec::new(),
+    };
 
-            Some(
+    styles
+        .iter()
+        .filter_map(|&style| {
+            let out = target.convert(style);
+            (out != target.text).then(|| {
                 CodeAction {
-                    title: item.to_string(),
+                    title: style.title().to_string(),
                     kind: Some(CodeActionKind::REFACTOR_INLINE),
-                    edit: Some(WorkspaceEdit::new(changes)),
+                    edit: Some(WorkspaceEdit::new(HashMap::from([(
+                        params.text_document.uri.clone(),
+                        vec![TextEdit::new(target.range, out)],
+                    )]))),
                     is_preferred: Some(true),
                     ..Default::default()
                 }
-                .into(),
-            )
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// 替换全部同名标识符，编辑在 resolve 时生成
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Rename {
+    style: CaseStyle,
+    word: String,
+    /// 是否替换所有已打开文档
+    pub all_documents: bool,
+}
+
+impl Rename {
+    pub fn apply<'a>(
+        &self,
+        documents: impl IntoIterator<Item = (&'a Url, &'a Rope)>,
+    ) -> HashMap<Url, Vec<TextEdit>> {
+        rename_changes(documents, &self.word, &self.style.convert(&self.word))
+    }
+}
+
+/// 选择内容为单个标识符时，提供替换文件内全部同名标识符的 action；
+/// 传入 `open_documents` 时再提供替换所有已打开文档的 action。
+pub(super) fn rename_actions(
+    doc: &Rope,
+    params: &CodeActionParams,
+    styles: &[CaseStyle],
+    open_documents: Option<&HashMap<Url, Rope>>,
+) -> Vec<(CodeAction, Rename)> {
+    let target = match CaseTarget::new(doc, params.range) {
+        Some(target) if target.text.chars().all(char_is_word) => target,
+        _ => return Vec::new(),
+    };
+    let uri = &params.text_document.uri;
+
+    // 只扫描一次，文件内只有当前一处时无需额外 action
+    let in_file = find_word_ranges(doc, &target.text).len() > 1;
+    let in_others = open_documents.is_some_and(|documents| {
+        documents
+            .iter()
+            .any(|(other, doc)| other != uri && !find_word_ranges(doc, &target.text).is_empty())
+    });
+
+    let mut items = Vec::new();
+    for &style in styles {
+        if style.convert(&target.text) == target.text {
+            continue;
+        }
+        for (enabled, all_documents, suffix) in [
+            (in_file, false, "all in file"),
+            (in_others, true, "all open documents"),
+        ] {
+            if !enabled {
+                continue;
+            }
+            let action = CodeAction {
+                title: format!("{} ({suffix})", style.title()),
+                kind: Some(CodeActionKind::REFACTOR_INLINE),
+                ..Default::default()
+            };
+            let rename = Rename {
+                style,
+                word: target.text.clone(),
+                all_documents,
+            };
+            items.push((action, rename));
+        }
+    }
+    items
+}
+
+/// 替换文档中所有完整匹配的标识符
+fn rename_changes<'a>(
+    documents: impl IntoIterator<Item = (&'a Url, &'a Rope)>,
+    word: &str,
+    new_text: &str,
+) -> HashMap<Url, Vec<TextEdit>> {
+    documents
+        .into_iter()
+        .filter_map(|(uri, doc)| {
+            let edits: Vec<TextEdit> = find_word_ranges(doc, word)
+                .into_iter()
+                .map(|range| TextEdit::new(range, new_text.to_owned()))
+                .collect();
+            (!edits.is_empty()).then(|| (uri.clone(), edits))
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use async_lsp::lsp_types::{
+        CodeActionContext, CodeActionParams, PartialResultParams, Position, Range,
+        TextDocumentIdentifier, Url, WorkDoneProgressParams,
+    };
+    use ropey::Rope;
+
+    use super::{CaseStyle, CaseTarget, case_actions, rename_actions};
+
+    #[test]
+    fn test_case_style_convert() {
+        let cases = [
+            (CaseStyle::Snake, "userId", "user_id"),
+            (CaseStyle::Kebab, "user_id", "user-id"),
+            (CaseStyle::Constant, "userId", "USER_ID"),
+            (CaseStyle::Title, "user_id", "User Id"),
+            (CaseStyle::Sentence, "user_id", "User id"),
+            (CaseStyle::Dot, "UserId", "user.id"),
+            (CaseStyle::Path, "user.id", "user/id"),
+            (CaseStyle::Train, "user/id", "User-Id"),
+            (CaseStyle::Camel, "user.id", "userId"),
+        ];
+
+        for (style, input, expected) in cases {
+            assert_eq!(style.convert(input), expected, "{style:?}");
+        }
+    }
+
+    #[test]
+    fn test_case_target_word_under_cursor() {
+        let doc = Rope::from_str("let 😀 = user_id;");
+        let target =
+            CaseTarget::new(&doc, Range::new(Position::new(0, 11), Position::new(0, 12))).unwrap();
+        assert_eq!(target.text, "user_id");
+        assert_eq!(target.range.start.character, 9);
+        assert_eq!(target.range.end.character, 16);
+        assert_eq!(target.convert(CaseStyle::Camel), "userId");
+
+        // UTF-16 列与字符索引不同时仍选中光标所在单词
+        let doc = Rope::from_str("😀😀 ab cd");
+        let target =
+            CaseTarget::new(&doc, Range::new(Position::new(0, 6), Position::new(0, 7))).unwrap();
+        assert_eq!(target.text, "ab");
+        assert_eq!(target.range.start.character, 5);
+        assert_eq!(target.range.end.character, 7);
+    }
+
+    #[test]
+    fn test_case_target_multi_line() {
+        let doc = Rope::from_str("  user_id\n\n  user_name\n");
+        let target =
+            CaseTarget::new(&doc, Range::new(Position::new(0, 0), Position::new(3, 0))).unwrap();
+        assert_eq!(
+            target.convert(CaseStyle::Pascal),
+            "  UserId\n\n  UserName\n"
+        );
+    }
+
+    #[test]
+    fn test_case_actions_all_occurrences() {
+        let uri = Url::parse("file:///a.rs").unwrap();
+        let other = Url::parse("file:///b.rs").unwrap();
+        let doc = Rope::from_str("let user_id = 1;\nprint(user_id, user_ids);\n");
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: Range::new(Position::new(0, 5), Position::new(0, 6)),
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let documents = HashMap::from([
+            (uri.clone(), doc.clone()),
+            (other.clone(), Rope::from_str("user_id\n")),
+        ]);
+
+        let actions = case_actions(&doc, &params, &[CaseStyle::Camel]);
+        assert_eq!(actions.len(), 1);
+
+        let renames = rename_actions(&doc, &params, &[CaseStyle::Camel], Some(&documents));
+        let titles: Vec<&str> = renames
+            .iter()
+            .map(|(action, _)| action.title.as_str())
+            .collect();
+        assert_eq!(
+            titles,
+            ["caseCamel (all in file)", "caseCamel (all open documents)"]
+        );
+        assert!(renames.iter().all(|(action, _)| action.edit.is_none()));
+
+        let changes = renames[0].1.apply([(&uri, &doc)]);
+        let edits = &changes[&uri];
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[1].range.start, Position::new(1, 6));
+        assert_eq!(edits[1].new_text, "userId");
+
+        assert!(renames[1].1.all_documents);
+        assert_eq!(renames[1].1.apply(&documents).len(), 2);
+
+        // 其他文档不包含时不提供
+        let documents = HashMap::from([(uri.clone(), doc.clone())]);
+        let renames = rename_actions(&doc, &params, &[CaseStyle::Camel], Some(&documents));
+        assert_eq!(renames.len(), 1);
+    }
+}
@@ -0,0 +1,311 @@
+use async_lsp::lsp_types::{CodeActionKind, CodeActionOrCommand, CodeActionParams};
+use base64::{Engine, engine::general_purpose};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use ropey::RopeSlice;
+
+use super::{code_action, trim_parts};
+
+/// 与 `encodeURIComponent` 一致，保留 `-_.~`
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// 编码与解码操作，解码只在选择内容可以完整解码时提供
+pub(crate) fn codec_actions(
+    range_content: RopeSlice,
+    params: &CodeActionParams,
+) -> Vec<CodeActionOrCommand> {
+    // 单字符选择视为光标
+    if params.range.start == params.range.end || range_content.len_chars() < 2 {
+        return Vec::new();
+    }
+
+    let raw = range_content.to_string();
+    // 整行选择时保留缩进与换行
+    let (leading, text, trailing) = trim_parts(&raw);
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let items: [(&str, Option<String>); 12] = [
+        (
+            "Base64 Encode",
+            Some(general_purpose::STANDARD.encode(text)),
+        ),
+        ("Base64 Decode", base64_decode(text)),
+        (
+            "URL Encode",
+            Some(utf8_percent_encode(text, URL_COMPONENT).to_string()),
+        ),
+        ("URL Decode", url_decode(text)),
+        ("HTML Escape", Some(html_escape(text))),
+        ("HTML Unescape", html_unescape(text)),
+        ("JSON String Escape", json_escape(text)),
+        ("JSON String Unescape", json_unescape(text)),
+        ("Rust String Escape", Some(text.escape_debug().to_string())),
+        ("Rust String Unescape", rust_unescape(text)),
+        ("Unicode Escape", Some(unicode_escape(text))),
+        ("Unicode Unescape", unicode_unescape(text)),
+    ];
+
+    items
+        .into_iter()
+        .filter_map(|(title, out)| {
+            let out = out.filter(|out| out != text)?;
+            Some(code_action(
+                title.to_string(),
+                CodeActionKind::REFACTOR_REWRITE,
+                &params.text_document.uri,
+                params.range,
+                format!("{leading}{out}{trailing}"),
+            ))
+        })
+        .collect()
+}
+
+fn base64_decode(text: &str) -> Option<String> {
+    let bytes = general_purpose::STANDARD
+        .decode(text)
+        .or_else(|_| general_purpose::URL_SAFE.decode(text))
+        .or_else(|_| general_purpose::STANDARD_NO_PAD.decode(text))
+        .or_else(|_| general_purpose::URL_SAFE_NO_PAD.decode(text))
+        .ok()?;
+    let out = String::from_utf8(bytes).ok()?;
+    // 解码结果应为可读文本
+    (!out.is_empty() && !out.chars().any(|c| c.is_control() && !c.is_whitespace())).then_some(out)
+}
+
+fn url_decode(text: &str) -> Option<String> {
+    if !text.contains('%') && !text.contains('+') {
+        return None;
+    }
+    let text = text.replace('+', " ");
+    percent_decode_str(&text)
+        .decode_utf8()
+        .ok()
+        .map(|s| s.into_owned())
+}
+
+fn html_escape(text: &str) -> String {
+    text.chars().fold(String::new(), |mut out, c| {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+        out
+    })
+}
+
+/// 解析 HTML 实体，遇到无法识别的实体时返回 `None`
+pub(crate) fn html_unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => '\u{a0}',
+            _ => {
+                let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+                char::from_u32(code)?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    Some(out)
+}
+
+fn json_escape(text: &str) -> Option<String> {
+    let quoted = serde_json::to_string(text).ok()?;
+    Some(quoted[1..quoted.len() - 1].to_owned())
+}
+
+/// 解析 JSON 字符串转义，兼容带引号的字符串
+fn json_unescape(text: &str) -> Option<String> {
+    if !text.contains('\\') {
+        return None;
+    }
+    let quoted = match text.starts_with('"') && text.ends_with('"') && text.len() > 1 {
+        true => text.to_owned(),
+        false => format!("\"{text}\""),
+    };
+    serde_json::from_str(&quoted).ok()
+}
+
+/// 解析 Rust 字符串转义
+fn rust_unescape(text: &str) -> Option<String> {
+    if !text.contains('\\') {
+        return None;
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let c = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let code = u8::from_str_radix(&hex, 16).ok().filter(u8::is_ascii)?;
+                code as char
+            }
+            'u' => parse_unicode_braces(&mut chars)?,
+            _ => return None,
+        };
+        out.push(c);
+    }
+
+    Some(out)
+}
+
+fn unicode_escape(text: &str) -> String {
+    text.chars().fold(String::new(), |mut out, c| {
+        match c.is_ascii() {
+            true => out.push(c),
+            false => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        }
+        out
+    })
+}
+
+/// 只解析 `\u{...}` 转义，其余内容原样保留
+fn unicode_unescape(text: &str) -> Option<String> {
+    if !text.contains("\\u{") {
+        return None;
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("\\u{") {
+        out.push_str(&rest[..start]);
+        let mut chars = rest[start + 2..].chars();
+        out.push(parse_unicode_braces(&mut chars)?);
+        rest = chars.as_str();
+    }
+    out.push_str(rest);
+
+    Some(out)
+}
+
+/// 解析 `{XXXX}` 形式的 Unicode 码点
+fn parse_unicode_braces(chars: &mut std::str::Chars) -> Option<char> {
+    if chars.next()? != '{' {
+        return None;
+    }
+    let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+    if hex.is_empty() || hex.len() > 6 {
+        return None;
+    }
+    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{
+        base64_decode, codec_actions, html_escape, html_unescape, json_escape, json_unescape,
+        rust_unescape, unicode_escape, unicode_unescape, url_decode,
+    };
+    use crate::action_inner::{action_outputs, test_params};
+
+    #[test]
+    fn test_line_selection_keeps_newline() {
+        let doc = Rope::from_str("aGVsbG8gd29ybGQ=\nnext\n");
+        let range = Range::new(Position::new(0, 0), Position::new(1, 0));
+        let outputs = action_outputs(codec_actions(doc.line(0), &test_params(range)));
+        let find = |title: &str| outputs.iter().find(|(t, _)| t == title).unwrap().1.clone();
+        assert_eq!(find("Base64 Decode"), "hello world\n");
+        assert_eq!(find("Base64 Encode"), "YUdWc2JHOGdkMjl5YkdRPQ==\n");
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(
+            base64_decode("aGVsbG8gd29ybGQ="),
+            Some("hello world".to_owned())
+        );
+        assert_eq!(base64_decode("5L2g5aW9"), Some("你好".to_owned()));
+        assert_eq!(base64_decode("hello world"), None);
+        assert_eq!(base64_decode("test"), None);
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode("a%20b%2Fc"), Some("a b/c".to_owned()));
+        assert_eq!(url_decode("%E4%BD%A0"), Some("你".to_owned()));
+        assert_eq!(url_decode("%FF"), None);
+        assert_eq!(url_decode("abc"), None);
+    }
+
+    #[test]
+    fn test_html() {
+        assert_eq!(
+            html_escape("<a href=\"x\">"),
+            "&lt;a href=&quot;x&quot;&gt;"
+        );
+        assert_eq!(
+            html_unescape("&lt;b&gt; &#65;&#x42; &amp;"),
+            Some("<b> AB &".to_owned())
+        );
+        assert_eq!(html_unescape("a & b"), None);
+        assert_eq!(html_unescape("&unknown;"), None);
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(json_escape("a\"b\n"), Some("a\\\"b\\n".to_owned()));
+        assert_eq!(json_unescape("a\\\"b\\n"), Some("a\"b\n".to_owned()));
+        assert_eq!(json_unescape("\"\\u4f60\""), Some("你".to_owned()));
+        assert_eq!(json_unescape("a\\qb"), None);
+    }
+
+    #[test]
+    fn test_rust_unescape() {
+        assert_eq!(
+            rust_unescape("a\\tb\\u{4f60}\\x41"),
+            Some("a\tb你A".to_owned())
+        );
+        assert_eq!(rust_unescape("\\q"), None);
+        assert_eq!(rust_unescape("plain"), None);
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(unicode_escape("a你😀"), "a\\u{4f60}\\u{1f600}");
+        assert_eq!(
+            unicode_unescape("a\\u{4f60}\\u{1f600}\\n"),
+            Some("a你😀\\n".to_owned())
+        );
+        assert_eq!(unicode_unescape("\\u{zz}"), None);
+    }
+}
@@ -0,0 +1,276 @@
+use async_lsp::lsp_types::{
+    CodeActionKind, CodeActionOrCommand, CodeActionParams, Position, Range, TextEdit,
+};
+use ropey::Rope;
+
+use super::code_action_edits;
+use crate::{
+    comment::{self, CommentTokens},
+    encoding::{get_range_content, selected_lines},
+};
+
+/// 行注释与块注释切换
+///
+/// Markdown 代码块内使用代码块语言的注释符号。
+pub(crate) fn comment_actions(
+    lang_id: &str,
+    doc: &Rope,
+    params: &CodeActionParams,
+) -> Vec<CodeActionOrCommand> {
+    let range = params.range;
+    let (start_line, end_line) = selected_lines(&range);
+    if end_line >= doc.len_lines() {
+        return Vec::new();
+    }
+
+    let tokens = match lang_id {
+        "markdown" => fence_language(doc, start_line, end_line)
+            .map_or_else(|| comment::tokens(lang_id), |lang| comment::tokens(&lang)),
+        _ => comment::tokens(lang_id),
+    };
+    let tokens = match tokens {
+        Some(tokens) => tokens,
+        None => return Vec::new(),
+    };
+
+    let uri = &params.text_document.uri;
+    let mut items = Vec::new();
+
+    if let Some(token) = tokens.line {
+        let edits = toggle_line_comment(doc, start_line, end_line, token);
+        if !edits.is_empty() {
+            items.push(code_action_edits(
+                "Toggle Line Comment".to_string(),
+                CodeActionKind::REFACTOR_REWRITE,
+                uri,
+                edits,
+            ));
+        }
+    }
+
+    if let Some(edit) = toggle_block_comment(doc, range, tokens) {
+        items.push(code_action_edits(
+            "Toggle Block Comment".to_string(),
+            CodeActionKind::REFACTOR_REWRITE,
+            uri,
+            vec![edit],
+        ));
+    }
+
+    items
+}
+
+/// 全部非空行已注释时取消注释，否则在最小缩进处对齐注释
+fn toggle_line_comment(doc: &Rope, start: usize, end: usize, token: &str) -> Vec<TextEdit> {
+    let lines: Vec<(usize, String)> = (start..=end)
+        .map(|idx| (idx, doc.line(idx).to_string()))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    let indent_of = |line: &str| line.chars().take_while(|c| c.is_whitespace()).count();
+
+    let commented = lines
+        .iter()
+        .all(|(_, line)| line.trim_start().starts_with(token));
+
+    if commented {
+        return lines
+            .iter()
+            .map(|(idx, line)| {
+                let indent = indent_of(line);
+                let rest = &line.trim_start()[token.len()..];
+                let len = token.chars().count() + usize::from(rest.starts_with(' '));
+                let start = utf16_col(doc, *idx, indent);
+                let end = utf16_col(doc, *idx, indent + len);
+                TextEdit::new(
+                    Range::new(
+                        Position::new(*idx as u32, start),
+                        Position::new(*idx as u32, end),
+                    ),
+                    String::new(),
+                )
+            })
+            .collect();
+    }
+
+    let indent = lines
+        .iter()
+        .map(|(_, line)| indent_of(line))
+        .min()
+        .unwrap_or_default();
+    lines
+        .iter()
+        .map(|(idx, _)| {
+            let pos = Position::new(*idx as u32, utf16_col(doc, *idx, indent));
+            TextEdit::new(Range::new(pos, pos), format!("{token} "))
+        })
+        .collect()
+}
+
+/// 有选择内容时包围选择内容，否则包围当前行去除缩进后的内容
+fn toggle_block_comment(doc: &Rope, range: Range, tokens: CommentTokens) -> Option<TextEdit> {
+    let (open, close) = tokens.block?;
+    let content = get_range_content(doc, &range)?;
+
+    let mut range = match range.start != range.end && content.len_chars() > 1 {
+        true => range,
+        false => {
+            let idx = range.start.line as usize;
+            let line = doc.line(idx).to_string();
+            let line = line.trim_end_matches(['\n', '\r']);
+            let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+            let len = line.chars().count();
+            if indent == len {
+                return None;
+            }
+            Range::new(
+                Position::new(idx as u32, utf16_col(doc, idx, indent)),
+                Position::new(idx as u32, utf16_col(doc, idx, len)),
+            )
+        }
+    };
+    // 整行选择时末尾换行留在注释之外
+    if range.end.line > range.start.line && range.end.character == 0 {
+        let idx = range.end.line as usize - 1;
+        let line = doc.line(idx).to_string();
+        let len = line.trim_end_matches(['\n', '\r']).chars().count();
+        range.end = Position::new(idx as u32, utf16_col(doc, idx, len));
+    }
+    let text = get_range_content(doc, &range)?.to_string();
+
+    let trimmed = text.trim();
+    let new_text = match trimmed
+        .strip_prefix(open)
+        .and_then(|s| s.strip_suffix(close))
+    {
+        Some(inner) => {
+            let inner = inner.strip_prefix(' ').unwrap_or(inner);
+            let inner = inner.strip_suffix(' ').unwrap_or(inner);
+            let start = text.len() - text.trim_start().len();
+            let end = text.trim_end().len();
+            format!("{}{inner}{}", &text[..start], &text[end..])
+        }
+        None => format!("{open} {text} {close}"),
+    };
+
+    Some(TextEdit::new(range, new_text))
+}
+
+/// 查找包含所选行的 Markdown 代码块语言
+fn fence_language(doc: &Rope, start: usize, end: usize) -> Option<String> {
+    let mut fence: Option<(String, String, usize)> = None;
+
+    for (idx, line) in doc.lines().enumerate() {
+        let line = line.to_string();
+        let trimmed = line.trim();
+        let marker: String = trimmed
+            .chars()
+            .take_while(|&c| c == '`' || c == '~')
+            .collect();
+
+        let is_fence = marker.len() >= 3 && marker.chars().all(|c| marker.starts_with(c));
+
+        match &fence {
+            // 结束标记不短于开始标记且不带其他内容
+            Some((open, lang, open_idx))
+                if is_fence
+                    && marker.len() >= open.len()
+                    && marker.starts_with(&open[..1])
+                    && trimmed.len() == marker.len() =>
+            {
+                if *open_idx < start && idx > end {
+                    return Some(lang.clone());
+                }
+                fence = None;
+            }
+            None if is_fence => {
+                if idx >= start {
+                    return None;
+                }
+                let info = trimmed[marker.len()..].trim();
+                let lang = info
+                    .split(|c: char| c.is_whitespace() || c == '{' || c == ',')
+                    .next()
+                    .unwrap_or_default();
+                fence = Some((marker, lang.to_string(), idx));
+            }
+            _ => {}
+        }
+
+        if idx > end && fence.is_none() {
+            return None;
+        }
+    }
+
+    None
+}
+
+fn utf16_col(doc: &Rope, line: usize, char_idx: usize) -> u32 {
+    doc.line(line).char_to_utf16_cu(char_idx) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{fence_language, toggle_block_comment, toggle_line_comment};
+    use crate::comment;
+
+    #[test]
+    fn test_toggle_line_comment() {
+        let doc = Rope::from_str("fn main() {\n    let a = 1;\n\n  // b\n}\n");
+        let edits = toggle_line_comment(&doc, 1, 3, "//");
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].range.start, Position::new(1, 2));
+        assert_eq!(edits[0].new_text, "// ");
+
+        let edits = toggle_line_comment(&doc, 3, 3, "//");
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.start, Position::new(3, 2));
+        assert_eq!(edits[0].range.end, Position::new(3, 5));
+        assert!(edits[0].new_text.is_empty());
+    }
+
+    #[test]
+    fn test_toggle_block_comment() {
+        let tokens = comment::tokens("html").unwrap();
+        let doc = Rope::from_str("  <p>hi</p>\n  <!-- <b/> -->\n");
+        let cursor = Range::new(Position::new(0, 3), Position::new(0, 4));
+        let edit = toggle_block_comment(&doc, cursor, tokens).unwrap();
+        assert_eq!(edit.range.start, Position::new(0, 2));
+        assert_eq!(edit.new_text, "<!-- <p>hi</p> -->");
+
+        let cursor = Range::new(Position::new(1, 3), Position::new(1, 4));
+        let edit = toggle_block_comment(&doc, cursor, tokens).unwrap();
+        assert_eq!(edit.new_text, "<b/>");
+    }
+
+    #[test]
+    fn test_toggle_block_comment_whole_lines() {
+        let tokens = comment::tokens("c").unwrap();
+        let doc = Rope::from_str("foo\nbar\n");
+        let lines = Range::new(Position::new(0, 0), Position::new(1, 0));
+        let edit = toggle_block_comment(&doc, lines, tokens).unwrap();
+        assert_eq!(edit.range.end, Position::new(0, 3));
+        assert_eq!(edit.new_text, "/* foo */");
+
+        let doc = Rope::from_str("/* foo */\r\nbar\n");
+        let lines = Range::new(Position::new(0, 0), Position::new(1, 0));
+        let edit = toggle_block_comment(&doc, lines, tokens).unwrap();
+        assert_eq!(edit.range.end, Position::new(0, 9));
+        assert_eq!(edit.new_text, "foo");
+    }
+
+    #[test]
+    fn test_fence_language() {
+        let doc = Rope::from_str("# title\n```rust\nlet a = 1;\n```\n\n~~~ python\nb = 2\n~~~\n");
+        assert_eq!(fence_language(&doc, 2, 2), Some("rust".to_string()));
+        assert_eq!(fence_language(&doc, 6, 6), Some("python".to_string()));
+        assert_eq!(fence_language(&doc, 0, 0), None);
+        assert_eq!(fence_language(&doc, 2, 4), None);
+    }
+}
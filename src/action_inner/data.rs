@@ -0,0 +1,269 @@
+use std::fmt::Display;
+
+use anyhow::{Result, anyhow};
+use async_lsp::lsp_types::{CodeAction, CodeActionKind, CodeActionParams, Range, TextEdit};
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+use serde_json::{Serializer, Value, ser::PrettyFormatter};
+
+use crate::encoding::{document_range, get_range_content};
+
+/// 结构化数据格式
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl DataFormat {
+    fn from_language_id(lang_id: &str) -> Option<DataFormat> {
+        match lang_id {
+            "json" | "jsonc" | "json5" => Some(DataFormat::Json),
+            "toml" => Some(DataFormat::Toml),
+            "yaml" => Some(DataFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// 根据内容判断格式，只尝试解析一种格式
+    ///
+    /// JSON 需以括号开头；TOML 与 YAML 至少包含两行内容，YAML 需为映射或序列。
+    fn detect(text: &str) -> Option<DataFormat> {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with(['{', '[']) && serde_json::from_str::<Value>(text).is_ok() {
+            return Some(DataFormat::Json);
+        }
+
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let first = lines.next()?;
+        lines.next()?;
+
+        // 首行为表头或 `=` 出现在 `:` 之前时按 TOML 解析
+        let is_toml = first.starts_with('[')
+            || first
+                .find('=')
+                .is_some_and(|eq| first.find(':').is_none_or(|colon| eq < colon));
+        match is_toml {
+            true => toml::from_str::<Value>(text)
+                .is_ok()
+                .then_some(DataFormat::Toml),
+            false => match serde_yaml_ng::from_str::<Value>(text) {
+                Ok(Value::Object(_) | Value::Array(_)) => Some(DataFormat::Yaml),
+                _ => None,
+            },
+        }
+    }
+
+    fn parse(&self, text: &str) -> Result<Value> {
+        let value = match self {
+            DataFormat::Json => serde_json::from_str(text)?,
+            DataFormat::Toml => toml::from_str(text)?,
+            DataFormat::Yaml => serde_yaml_ng::from_str(text)?,
+        };
+        Ok(value)
+    }
+}
+
+impl Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DataFormat::Json => "JSON",
+            DataFormat::Toml => "TOML",
+            DataFormat::Yaml => "YAML",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 延迟到 resolve 时执行的格式转换
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Reformat {
+    pub from: DataFormat,
+    pub to: DataFormat,
+    pub minify: bool,
+    pub range: Range,
+}
+
+impl Reformat {
+    fn title(&self) -> String {
+        match (self.from == self.to, self.minify) {
+            (true, true) => format!("Minify {}", self.from),
+            (true, false) => format!("Pretty-print {}", self.from),
+            (false, _) => format!("Convert {} to {}", self.from, self.to),
+        }
+    }
+
+    /// 转换选择区域，失败时返回错误而不产生编辑
+    pub fn apply(&self, doc: &Rope, indent: &str) -> Result<TextEdit> {
+        let text = get_range_content(doc, &self.range)
+            .ok_or_else(|| anyhow!("range out of document"))?
+            .to_string();
+        let value = self.from.parse(&text)?;
+
+        let mut out = match self.to {
+            DataFormat::Json if self.minify => serde_json::to_string(&value)?,
+            DataFormat::Json => {
+                let mut buf = Vec::new();
+                let mut ser = Serializer::with_formatter(
+                    &mut buf,
+                    PrettyFormatter::with_indent(indent.as_bytes()),
+                );
+                value.serialize(&mut ser)?;
+                String::from_utf8(buf)?
+            }
+            DataFormat::Toml => toml::to_string_pretty(&value)?,
+            DataFormat::Yaml => serde_yaml_ng::to_string(&value)?,
+        };
+
+        // 保持原有的结尾换行
+        match text.ends_with('\n') {
+            true if !out.ends_with('\n') => out.push('\n'),
+            false => out.truncate(out.trim_end_matches('\n').len()),
+            _ => {}
+        }
+
+        Ok(TextEdit::new(self.range, out))
+    }
+}
+
+/// JSON、TOML、YAML 格式化与互相转换
+///
+/// 没有选择内容时作用于整个文档，实际转换在 resolve 时执行。
+pub(crate) fn data_actions(
+    lang_id: &str,
+    doc: &Rope,
+    params: &CodeActionParams,
+) -> Vec<(CodeAction, Reformat)> {
+    let selected = get_range_content(doc, &params.range)
+        .filter(|content| content.len_chars() > 1 && !content.chars().all(char::is_whitespace));
+
+    let (range, from) = match selected {
+        // 选择内容需能够解析，数据文件中按文件格式解析
+        Some(content) => {
+            let text = content.to_string();
+            let format = match DataFormat::from_language_id(lang_id) {
+                Some(format) => format.parse(&text).ok().map(|_| format),
+                None => DataFormat::detect(&text),
+            };
+            match format {
+                Some(format) => (params.range, format),
+                None => return Vec::new(),
+            }
+        }
+        None => match DataFormat::from_language_id(lang_id) {
+            Some(format) => (document_range(doc), format),
+            None => return Vec::new(),
+        },
+    };
+
+    let mut targets = Vec::new();
+    if from == DataFormat::Json {
+        targets.push((DataFormat::Json, false));
+        targets.push((DataFormat::Json, true));
+    }
+    for to in [DataFormat::Json, DataFormat::Toml, DataFormat::Yaml] {
+        if to != from {
+            targets.push((to, false));
+        }
+    }
+
+    targets
+        .into_iter()
+        .map(|(to, minify)| {
+            let reformat = Reformat {
+                from,
+                to,
+                minify,
+                range,
+            };
+            let action = CodeAction {
+                title: reformat.title(),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                ..Default::default()
+            };
+            (action, reformat)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{DataFormat, Reformat};
+    use crate::encoding::document_range;
+
+    fn apply(text: &str, from: DataFormat, to: DataFormat, minify: bool) -> String {
+        let doc = Rope::from_str(text);
+        let reformat = Reformat {
+            from,
+            to,
+            minify,
+            range: document_range(&doc),
+        };
+        reformat.apply(&doc, "  ").unwrap().new_text
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(DataFormat::detect("{\"a\": 1}"), Some(DataFormat::Json));
+        assert_eq!(
+            DataFormat::detect("[a]\nb = \"x:y\""),
+            Some(DataFormat::Toml)
+        );
+        assert_eq!(DataFormat::detect("a: 1\nb: [2]"), Some(DataFormat::Yaml));
+        assert_eq!(DataFormat::detect("plain text"), None);
+        // 单行赋值或键值对不视为结构化数据
+        assert_eq!(DataFormat::detect("a = 1"), None);
+        assert_eq!(DataFormat::detect("k: v"), None);
+        assert_eq!(DataFormat::detect("see [1]"), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let text = "{\"b\":1,\"a\":[1,2]}\n";
+        assert_eq!(
+            apply(text, DataFormat::Json, DataFormat::Json, false),
+            "{\n  \"b\": 1,\n  \"a\": [\n    1,\n    2\n  ]\n}\n"
+        );
+        assert_eq!(
+            apply("{ \"a\": 1 }", DataFormat::Json, DataFormat::Json, true),
+            "{\"a\":1}"
+        );
+    }
+
+    #[test]
+    fn test_convert() {
+        let text = "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n";
+        assert_eq!(
+            apply(text, DataFormat::Toml, DataFormat::Yaml, false),
+            "package:\n  name: demo\n  version: 0.1.0\n"
+        );
+        assert_eq!(
+            apply(
+                "package:\n  name: demo\n",
+                DataFormat::Yaml,
+                DataFormat::Toml,
+                false
+            ),
+            "[package]\nname = \"demo\"\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_error() {
+        let doc = Rope::from_str("[1, 2]");
+        let reformat = Reformat {
+            from: DataFormat::Json,
+            to: DataFormat::Toml,
+            minify: false,
+            range: Range::new(Position::new(0, 0), Position::new(0, 6)),
+        };
+        assert!(reformat.apply(&doc, "  ").is_err());
+    }
+}
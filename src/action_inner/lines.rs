@@ -0,0 +1,331 @@
+use std::cmp::Ordering;
+
+use async_lsp::lsp_types::{CodeActionKind, CodeActionOrCommand, CodeActionParams};
+use rand::seq::SliceRandom;
+use ropey::RopeSlice;
+
+use super::code_action;
+
+/// 拆分单行时支持的分隔符
+const SPLIT_DELIMITERS: [(&str, &str); 4] = [
+    (",", "Comma"),
+    (";", "Semicolon"),
+    ("|", "Pipe"),
+    ("\t", "Tab"),
+];
+
+/// 按列排序时最多提供的列数
+const MAX_SORT_COLUMNS: usize = 4;
+
+/// 多行文本操作: 排序、去重、反转、打乱、合并与拆分
+pub(crate) fn line_actions(
+    range_content: RopeSlice,
+    params: &CodeActionParams,
+) -> Vec<CodeActionOrCommand> {
+    if params.range.start == params.range.end {
+        return Vec::new();
+    }
+
+    let content = range_content.to_string();
+    // 沿用文档的换行符
+    let eol = match content.contains("\r\n") {
+        true => "\r\n",
+        false => "\n",
+    };
+    // 整行选择时保留结尾换行
+    let (body, newline) = match content.strip_suffix(eol) {
+        Some(body) => (body, eol),
+        None => (content.as_str(), ""),
+    };
+    let lines: Vec<&str> = body.lines().collect();
+
+    let items: Vec<(String, String)> = match lines.len() {
+        // 仅对整行选择提供拆分，避免普通选择时出现过多 action
+        0 | 1 if newline.is_empty() => Vec::new(),
+        0 | 1 => split_items(body, eol),
+        _ => multi_line_items(&lines, eol),
+    };
+
+    items
+        .into_iter()
+        .filter(|(_, out)| out != body)
+        .map(|(title, out)| {
+            code_action(
+                title,
+                CodeActionKind::REFACTOR_REWRITE,
+                &params.text_document.uri,
+                params.range,
+                format!("{out}{newline}"),
+            )
+        })
+        .collect()
+}
+
+fn split_items(line: &str, eol: &str) -> Vec<(String, String)> {
+    SPLIT_DELIMITERS
+        .iter()
+        .filter_map(|(delimiter, name)| {
+            let parts: Vec<&str> = line.split(delimiter).map(str::trim).collect();
+            (parts.len() > 1 && parts.iter().all(|part| !part.is_empty()))
+                .then(|| (format!("Split by {name}"), parts.join(eol)))
+        })
+        .collect()
+}
+
+fn multi_line_items(lines: &[&str], eol: &str) -> Vec<(String, String)> {
+    let sorted = |cmp: fn(&str, &str) -> Ordering| {
+        let mut lines = lines.to_vec();
+        lines.sort_by(|a, b| cmp(a, b));
+        lines.join(eol)
+    };
+
+    let mut items = vec![
+        ("Sort Lines".to_string(), sorted(|a, b| a.cmp(b))),
+        (
+            "Sort Lines (Case Insensitive)".to_string(),
+            sorted(|a, b| a.to_lowercase().cmp(&b.to_lowercase())),
+        ),
+        ("Sort Lines (Natural)".to_string(), sorted(natural_cmp)),
+        ("Sort Lines (Numeric)".to_string(), sorted(numeric_cmp)),
+    ];
+
+    for column in 1..column_count(lines).min(MAX_SORT_COLUMNS) {
+        let mut sorted = lines.to_vec();
+        sorted.sort_by(|a, b| {
+            natural_cmp(
+                column_field(a, column).unwrap_or_default(),
+                column_field(b, column).unwrap_or_default(),
+            )
+        });
+        items.push((
+            format!("Sort Lines by Column {}", column + 1),
+            sorted.join(eol),
+        ));
+    }
+
+    let mut unique = Vec::new();
+    for line in lines {
+        if !unique.contains(line) {
+            unique.push(*line);
+        }
+    }
+    items.push(("Unique Lines".to_string(), unique.join(eol)));
+
+    items.push((
+        "Reverse Lines".to_string(),
+        lines.iter().rev().copied().collect::<Vec<_>>().join(eol),
+    ));
+
+    let mut shuffled = lines.to_vec();
+    shuffled.shuffle(&mut rand::rng());
+    // 避免打乱后与原文相同导致 action 不显示
+    if shuffled == lines {
+        shuffled.rotate_left(1);
+    }
+    items.push(("Shuffle Lines".to_string(), shuffled.join(eol)));
+
+    items.push(("Join Lines".to_string(), join_lines(lines, " ")));
+    items.push(("Join Lines with Comma".to_string(), join_lines(lines, ", ")));
+
+    items
+}
+
+/// 合并多行，保留第一行缩进，去除其余行首尾空白
+fn join_lines(lines: &[&str], separator: &str) -> String {
+    let indent: String = lines[0].chars().take_while(|c| c.is_whitespace()).collect();
+    let joined = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator);
+    format!("{indent}{joined}")
+}
+
+/// 行内的列，包含制表符时按制表符拆分，否则按空白拆分
+fn column_field(line: &str, column: usize) -> Option<&str> {
+    match line.contains('\t') {
+        true => line.split('\t').nth(column).map(str::trim),
+        false => line.split_whitespace().nth(column),
+    }
+}
+
+/// 非空行中最少的列数
+fn column_count(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.contains('\t') {
+            true => line.split('\t').count(),
+            false => line.split_whitespace().count(),
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// 自然排序，数字部分按数值比较: `a2` < `a10`
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x: String =
+                    std::iter::from_fn(|| a_chars.next_if(char::is_ascii_digit)).collect();
+                let y: String =
+                    std::iter::from_fn(|| b_chars.next_if(char::is_ascii_digit)).collect();
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ord = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// 按行首数值排序，没有数值的行排在最后
+fn numeric_cmp(a: &str, b: &str) -> Ordering {
+    match (leading_number(a), leading_number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let end = line
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map(|(i, _)| i)
+        .unwrap_or(line.len());
+    line[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use async_lsp::lsp_types::{
+        CodeActionContext, CodeActionOrCommand, CodeActionParams, PartialResultParams, Position,
+        Range, TextDocumentIdentifier, Url, WorkDoneProgressParams,
+    };
+    use ropey::Rope;
+
+    use super::{line_actions, natural_cmp, numeric_cmp};
+
+    fn actions(text: &str, range: Range) -> Vec<(String, String)> {
+        let doc = Rope::from_str(text);
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(Url::parse("file:///a.txt").unwrap()),
+            range,
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let content = crate::encoding::get_range_content(&doc, &range).unwrap();
+
+        line_actions(content, &params)
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => {
+                    let edits = action.edit?.changes?.into_values().next()?;
+                    Some((action.title, edits[0].new_text.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn find<'a>(items: &'a [(String, String)], title: &str) -> Option<&'a str> {
+        items
+            .iter()
+            .find(|(t, _)| t == title)
+            .map(|(_, out)| out.as_str())
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("a2", "a10"), Ordering::Less);
+        assert_eq!(natural_cmp("a010", "a9"), Ordering::Greater);
+        assert_eq!(natural_cmp("B1", "a2"), Ordering::Greater);
+        assert_eq!(numeric_cmp("-3 x", "2 y"), Ordering::Less);
+        assert_eq!(numeric_cmp("x", "2 y"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_multi_line_actions() {
+        let items = actions(
+            "b 10\na 9\nb 10\nC 1\n",
+            Range::new(Position::new(0, 0), Position::new(4, 0)),
+        );
+
+        assert_eq!(find(&items, "Sort Lines"), Some("C 1\na 9\nb 10\nb 10\n"));
+        assert_eq!(
+            find(&items, "Sort Lines (Case Insensitive)"),
+            Some("a 9\nb 10\nb 10\nC 1\n")
+        );
+        assert_eq!(
+            find(&items, "Sort Lines by Column 2"),
+            Some("C 1\na 9\nb 10\nb 10\n")
+        );
+        assert_eq!(find(&items, "Unique Lines"), Some("b 10\na 9\nC 1\n"));
+        assert_eq!(
+            find(&items, "Reverse Lines"),
+            Some("C 1\nb 10\na 9\nb 10\n")
+        );
+        assert_eq!(
+            find(&items, "Join Lines with Comma"),
+            Some("b 10, a 9, b 10, C 1\n")
+        );
+    }
+
+    #[test]
+    fn test_split_actions() {
+        let items = actions(
+            "a, b, c\n",
+            Range::new(Position::new(0, 0), Position::new(1, 0)),
+        );
+        assert_eq!(find(&items, "Split by Comma"), Some("a\nb\nc\n"));
+        assert_eq!(find(&items, "Split by Pipe"), None);
+        assert!(find(&items, "Sort Lines").is_none());
+
+        // 行内选择与空字段不提供拆分
+        let items = actions(
+            "a, b, c",
+            Range::new(Position::new(0, 0), Position::new(0, 7)),
+        );
+        assert!(items.is_empty());
+        let items = actions("a,\n", Range::new(Position::new(0, 0), Position::new(1, 0)));
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn test_crlf_line_actions() {
+        let items = actions(
+            "b\r\na\r\n",
+            Range::new(Position::new(0, 0), Position::new(2, 0)),
+        );
+        assert_eq!(find(&items, "Sort Lines"), Some("a\r\nb\r\n"));
+
+        let items = actions(
+            "a;b\r\n",
+            Range::new(Position::new(0, 0), Position::new(1, 0)),
+        );
+        assert_eq!(find(&items, "Split by Semicolon"), Some("a\r\nb\r\n"));
+    }
+}
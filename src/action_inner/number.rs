@@ -0,0 +1,216 @@
+use async_lsp::lsp_types::{CodeActionKind, CodeActionOrCommand, CodeActionParams};
+use ropey::RopeSlice;
+use time::{
+    Date, OffsetDateTime, PrimitiveDateTime, Time,
+    format_description::well_known::{Iso8601, Rfc3339},
+    macros::format_description,
+};
+
+use super::{code_action, trim_parts};
+use crate::variables::init_time_offset;
+
+/// 数值与时间转换
+pub(crate) fn number_actions(
+    range_content: RopeSlice,
+    params: &CodeActionParams,
+) -> Vec<CodeActionOrCommand> {
+    // 单字符选择视为光标
+    if params.range.start == params.range.end || range_content.len_chars() < 2 {
+        return Vec::new();
+    }
+
+    let raw = range_content.to_string();
+    // 整行选择时保留缩进与换行
+    let (leading, text, trailing) = trim_parts(&raw);
+
+    let mut items: Vec<(&str, String)> = Vec::new();
+
+    if let Some((value, radix)) = parse_integer(text) {
+        for (title, to) in [
+            ("To Decimal", 10),
+            ("To Hex", 16),
+            ("To Octal", 8),
+            ("To Binary", 2),
+        ] {
+            if to != radix {
+                items.push((title, format_radix(value, to)));
+            }
+        }
+        if value >= 1024 {
+            items.push(("To Byte Size", format_bytes(value)));
+        }
+        if radix == 10 {
+            items.extend(timestamp_to_iso(text).map(|out| ("To ISO-8601", out)));
+        }
+    } else if let Some(bytes) = parse_bytes(text) {
+        items.push(("To Bytes", bytes.to_string()));
+    } else if let Some(time) = parse_datetime(text) {
+        items.push(("To Unix Timestamp", time.unix_timestamp().to_string()));
+        items.push((
+            "To Unix Timestamp (ms)",
+            (time.unix_timestamp_nanos() / 1_000_000).to_string(),
+        ));
+    }
+
+    items
+        .into_iter()
+        .filter(|(_, out)| out != text)
+        .map(|(title, out)| {
+            code_action(
+                title.to_string(),
+                CodeActionKind::REFACTOR_REWRITE,
+                &params.text_document.uri,
+                params.range,
+                format!("{leading}{out}{trailing}"),
+            )
+        })
+        .collect()
+}
+
+/// 解析整数，支持 `0x`、`0o`、`0b` 前缀及 `_` 分隔符
+fn parse_integer(text: &str) -> Option<(u64, u32)> {
+    let text = text.replace('_', "");
+    let lower = text.to_ascii_lowercase();
+    let (digits, radix) = match lower.get(..2) {
+        Some("0x") => (&lower[2..], 16),
+        Some("0o") => (&lower[2..], 8),
+        Some("0b") => (&lower[2..], 2),
+        _ => (lower.as_str(), 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    u64::from_str_radix(digits, radix)
+        .ok()
+        .map(|value| (value, radix))
+}
+
+fn format_radix(value: u64, radix: u32) -> String {
+    match radix {
+        16 => format!("{value:#x}"),
+        8 => format!("{value:#o}"),
+        2 => format!("{value:#b}"),
+        _ => value.to_string(),
+    }
+}
+
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// 格式化为二进制单位的字节大小，如 `1.5 MiB`
+fn format_bytes(value: u64) -> String {
+    let mut size = value as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    let size = format!("{size:.2}");
+    let size = size.trim_end_matches('0').trim_end_matches('.');
+    format!("{size} {}", BYTE_UNITS[unit])
+}
+
+/// 解析字节大小，`KB` 等十进制单位按 1000 计算，`KiB` 与单字母单位按 1024 计算
+fn parse_bytes(text: &str) -> Option<u64> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .filter(|&i| i > 0)?;
+    let size: f64 = text[..split].parse().ok()?;
+    let unit = text[split..].trim().to_ascii_lowercase();
+
+    let (prefix, base) = match unit.as_str() {
+        "b" => return Some(size as u64).filter(|_| size.fract() == 0.0),
+        u if u.len() == 1 => (u, 1024u64),
+        u if u.len() == 2 && u.ends_with('b') => (&u[..1], 1000),
+        u if u.len() == 3 && u.ends_with("ib") => (&u[..1], 1024),
+        _ => return None,
+    };
+    let exp = "kmgtpe".find(prefix)? as i32 + 1;
+    let bytes = size * (base as f64).powi(exp);
+    (bytes <= u64::MAX as f64).then_some(bytes.round() as u64)
+}
+
+/// 10 位数字视为秒级时间戳，13 位数字视为毫秒级时间戳
+fn timestamp_to_iso(text: &str) -> Option<String> {
+    let value: i64 = text.parse().ok()?;
+    let time = match text.len() {
+        10 => OffsetDateTime::from_unix_timestamp(value).ok()?,
+        13 => OffsetDateTime::from_unix_timestamp_nanos(value as i128 * 1_000_000).ok()?,
+        _ => return None,
+    };
+    time.to_offset(*init_time_offset()).format(&Rfc3339).ok()
+}
+
+/// 解析 ISO-8601 时间，未带时区时使用本地时区
+fn parse_datetime(text: &str) -> Option<OffsetDateTime> {
+    if let Ok(time) = OffsetDateTime::parse(text, &Rfc3339) {
+        return Some(time);
+    }
+    if let Ok(time) = OffsetDateTime::parse(text, &Iso8601::DEFAULT) {
+        return Some(time);
+    }
+
+    let offset = *init_time_offset();
+    let datetime =
+        format_description!("[year]-[month]-[day][first [T] [ ]][hour]:[minute]:[second]");
+    if let Ok(time) = PrimitiveDateTime::parse(text, datetime) {
+        return Some(time.assume_offset(offset));
+    }
+    let date = Date::parse(text, format_description!("[year]-[month]-[day]")).ok()?;
+    Some(PrimitiveDateTime::new(date, Time::MIDNIGHT).assume_offset(offset))
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{
+        format_bytes, format_radix, number_actions, parse_bytes, parse_datetime, parse_integer,
+    };
+    use crate::action_inner::{action_outputs, test_params};
+
+    #[test]
+    fn test_line_selection_keeps_newline() {
+        let doc = Rope::from_str("  255\nnext\n");
+        let range = Range::new(Position::new(0, 0), Position::new(1, 0));
+        let outputs = action_outputs(number_actions(doc.line(0), &test_params(range)));
+        let hex = outputs.iter().find(|(title, _)| title == "To Hex").unwrap();
+        assert_eq!(hex.1, "  0xff\n");
+    }
+
+    #[test]
+    fn test_parse_integer() {
+        assert_eq!(parse_integer("255"), Some((255, 10)));
+        assert_eq!(parse_integer("0xFF"), Some((255, 16)));
+        assert_eq!(parse_integer("0o17"), Some((15, 8)));
+        assert_eq!(parse_integer("0b1010_1010"), Some((170, 2)));
+        assert_eq!(parse_integer("1_000"), Some((1000, 10)));
+        assert_eq!(parse_integer("0x"), None);
+        assert_eq!(parse_integer("12a"), None);
+        assert_eq!(format_radix(255, 16), "0xff");
+        assert_eq!(format_radix(5, 2), "0b101");
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(format_bytes(1024), "1 KiB");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+        assert_eq!(format_bytes(1000), "1000 B");
+        assert_eq!(parse_bytes("1.5 MiB"), Some(1536 * 1024));
+        assert_eq!(parse_bytes("10KB"), Some(10_000));
+        assert_eq!(parse_bytes("2G"), Some(2 << 30));
+        assert_eq!(parse_bytes("12 apples"), None);
+        assert_eq!(parse_bytes("MiB"), None);
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let time = parse_datetime("2024-01-02T03:04:05Z").unwrap();
+        assert_eq!(time.unix_timestamp(), 1_704_164_645);
+        let time = parse_datetime("2024-01-02T03:04:05+08:00").unwrap();
+        assert_eq!(time.unix_timestamp(), 1_704_135_845);
+        assert!(parse_datetime("2024-01-02 03:04:05").is_some());
+        assert!(parse_datetime("2024-01-02").is_some());
+        assert!(parse_datetime("yesterday").is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use async_lsp::lsp_types::{
+    CodeActionKind, CodeActionOrCommand, CodeActionParams, Range, TextEdit,
+};
+use ropey::Rope;
+
+use super::code_action_edits;
+use crate::encoding::{OffsetEncoding, char_to_lsp_pos, lsp_pos_to_pos};
+
+const QUOTES: [(char, &str); 3] = [
+    ('"', "Use Double Quotes"),
+    ('\'', "Use Single Quotes"),
+    ('`', "Use Backticks"),
+];
+
+const BRACKETS: [(char, char, &str); 3] = [
+    ('(', ')', "Swap to Parentheses"),
+    ('[', ']', "Swap to Square Brackets"),
+    ('{', '}', "Swap to Curly Braces"),
+];
+
+/// 查找括号时最多扫描的字符数
+const MAX_SCAN: usize = 10_000;
+
+/// 切换光标所在字符串的引号，以及包围光标的括号
+pub(crate) fn quote_actions(doc: &Rope, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+    let cursor = match lsp_pos_to_pos(doc, params.range.start, OffsetEncoding::Utf16) {
+        Ok(cursor) if cursor < doc.len_chars() => cursor,
+        _ => return Vec::new(),
+    };
+    let end = lsp_pos_to_pos(doc, params.range.end, OffsetEncoding::Utf16).unwrap_or(cursor);
+
+    let mut items = Vec::new();
+
+    if let Some((start, close, quote)) =
+        find_literal(doc, cursor).filter(|&(_, close, _)| close >= end.saturating_sub(1))
+    {
+        let body: String = doc.slice(start + 1..close).chars().collect();
+        let range = Range::new(char_to_lsp_pos(doc, start), char_to_lsp_pos(doc, close + 1));
+        for (to, title) in QUOTES.into_iter().filter(|&(to, _)| to != quote) {
+            let new_text = format!("{to}{}{to}", requote(&body, quote, to));
+            items.push((title, vec![TextEdit::new(range, new_text)]));
+        }
+    }
+
+    if let Some((open, close)) = find_brackets(doc, cursor) {
+        let current = doc.char(open);
+        for (to_open, to_close, title) in BRACKETS.into_iter().filter(|&(o, _, _)| o != current) {
+            let edits = [(open, to_open), (close, to_close)]
+                .into_iter()
+                .map(|(idx, c)| {
+                    let range =
+                        Range::new(char_to_lsp_pos(doc, idx), char_to_lsp_pos(doc, idx + 1));
+                    TextEdit::new(range, c.to_string())
+                })
+                .collect();
+            items.push((title, edits));
+        }
+    }
+
+    items
+        .into_iter()
+        .map(|(title, edits)| {
+            code_action_edits(
+                title.to_string(),
+                CodeActionKind::REFACTOR_REWRITE,
+                &params.text_document.uri,
+                edits,
+            )
+        })
+        .collect()
+}
+
+/// 查找光标所在行中包含光标的字符串，返回起止引号的位置及引号
+fn find_literal(doc: &Rope, cursor: usize) -> Option<(usize, usize, char)> {
+    line_literals(doc, cursor)
+        .into_iter()
+        .find(|&(start, close)| start <= cursor && cursor <= close)
+        .map(|(start, close)| (start, close, doc.char(start)))
+}
+
+/// 字符所在行中的字符串字面量，返回起止引号的位置
+fn line_literals(doc: &Rope, idx: usize) -> Vec<(usize, usize)> {
+    let line_idx = doc.char_to_line(idx);
+    let line_start = doc.line_to_char(line_idx);
+    let chars: Vec<char> = doc.line(line_idx).chars().collect();
+
+    let mut literals = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !QUOTES.iter().any(|&(q, _)| q == chars[i]) || !opens_literal(&chars, i) {
+            i += 1;
+            continue;
+        }
+        match find_closing_quote(&chars, i + 1, chars[i]) {
+            Some(close) => {
+                literals.push((line_start + i, line_start + close));
+                i = close + 1;
+            }
+            // 未闭合的引号
+            None => i += 1,
+        }
+    }
+    literals
+}
+
+/// 引号前为单词字符时视为英文撇号，`r"`、`b'`、`f"` 等字符串前缀除外
+fn opens_literal(chars: &[char], quote: usize) -> bool {
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let prefix: String = chars[..quote]
+        .iter()
+        .rev()
+        .take_while(|c| is_word(c))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    prefix.is_empty()
+        || prefix == "L"
+        || ["r", "b", "f", "u", "c", "br", "rb", "fr", "rf", "u8"]
+            .contains(&prefix.to_lowercase().as_str())
+}
+
+fn find_closing_quote(chars: &[char], from: usize, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate().skip(from) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' | '\r' => return None,
+            c if c == quote => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 取消原引号的转义，并转义新的引号
+fn requote(body: &str, from: char, to: char) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == from => out.push(next),
+                Some(next) => {
+                    out.push(c);
+                    out.push(next);
+                }
+                None => out.push(c),
+            },
+            c if c == to => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 查找包围光标的括号，返回左右括号的位置
+fn find_brackets(doc: &Rope, cursor: usize) -> Option<(usize, usize)> {
+    let is_open = |c: char| BRACKETS.iter().any(|&(o, _, _)| o == c);
+    let is_close = |c: char| BRACKETS.iter().any(|&(_, cl, _)| cl == c);
+    let pair = |o: char| {
+        BRACKETS
+            .iter()
+            .find(|&&(open, _, _)| open == o)
+            .map(|&(_, c, _)| c)
+    };
+
+    // 跳过字符串中的括号，按行缓存字符串位置
+    let mut literals: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    let mut in_literal = |idx: usize| {
+        literals
+            .entry(doc.char_to_line(idx))
+            .or_insert_with(|| line_literals(doc, idx))
+            .iter()
+            .any(|&(start, close)| start <= idx && idx <= close)
+    };
+
+    // 光标位于右括号时查找与之匹配的左括号
+    let from = match is_close(doc.char(cursor)) {
+        true => cursor.checked_sub(1)?,
+        false => cursor,
+    };
+
+    let mut depth = 0usize;
+    let mut open = None;
+    for idx in (from.saturating_sub(MAX_SCAN)..=from).rev() {
+        let c = doc.char(idx);
+        if (is_open(c) || is_close(c)) && in_literal(idx) {
+            continue;
+        }
+        if is_close(c) {
+            depth += 1;
+        } else if is_open(c) {
+            if depth == 0 {
+                open = Some(idx);
+                break;
+            }
+            depth -= 1;
+        }
+    }
+    let open = open?;
+    let close_char = pair(doc.char(open))?;
+
+    let mut depth = 0usize;
+    let end = (open + MAX_SCAN).min(doc.len_chars());
+    for idx in open + 1..end {
+        let c = doc.char(idx);
+        if (is_open(c) || is_close(c)) && in_literal(idx) {
+            continue;
+        }
+        if is_open(c) {
+            depth += 1;
+        } else if is_close(c) {
+            if depth == 0 {
+                return (c == close_char).then_some((open, idx));
+            }
+            depth -= 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use ropey::Rope;
+
+    use super::{find_brackets, find_literal, requote};
+
+    #[test]
+    fn test_find_literal() {
+        let doc = Rope::from_str("let s = \"a \\\" b\"; // don't\n");
+        assert_eq!(find_literal(&doc, 10), Some((8, 15, '"')));
+        assert_eq!(find_literal(&doc, 8), Some((8, 15, '"')));
+        assert_eq!(find_literal(&doc, 2), None);
+        assert_eq!(find_literal(&doc, 24), None);
+
+        // 英文撇号不构成字符串，字符串前缀仍可识别
+        let doc = Rope::from_str("I don't think it won't work\n");
+        assert_eq!(find_literal(&doc, 10), None);
+        let doc = Rope::from_str("x = f'{a}' + r\"b\"\n");
+        assert_eq!(find_literal(&doc, 6), Some((5, 9, '\'')));
+        assert_eq!(find_literal(&doc, 15), Some((14, 16, '"')));
+    }
+
+    #[test]
+    fn test_requote() {
+        assert_eq!(requote("it's \\\"ok\\\"", '"', '\''), "it\\'s \"ok\"");
+        assert_eq!(requote("a\\nb", '"', '`'), "a\\nb");
+    }
+
+    #[test]
+    fn test_find_brackets() {
+        let doc = Rope::from_str("f(a, [b, c], {d})");
+        assert_eq!(find_brackets(&doc, 7), Some((5, 10)));
+        assert_eq!(find_brackets(&doc, 3), Some((1, 16)));
+        assert_eq!(find_brackets(&doc, 10), Some((5, 10)));
+        assert_eq!(find_brackets(&doc, 16), Some((1, 16)));
+        assert_eq!(find_brackets(&doc, 0), None);
+
+        // 忽略字符串中的括号
+        let doc = Rope::from_str("f(\")\", [x])");
+        assert_eq!(find_brackets(&doc, 10), Some((1, 10)));
+        assert_eq!(find_brackets(&doc, 8), Some((7, 9)));
+    }
+}
@@ -0,0 +1,156 @@
+// action 命令审计日志
+//
+// 以 JSON lines 格式记录 hx-lsp 执行过的 action 命令，默认关闭。
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Output,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tracing::error;
+
+use crate::loader::state_dir;
+
+/// 输出内容最大保留字符数
+const MAX_OUTPUT_CHARS: usize = 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 日志文件路径
+pub fn log_path() -> PathBuf {
+    state_dir().join("action-log.jsonl")
+}
+
+/// 单条执行记录
+#[derive(Serialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub time: String,
+    pub action: String,
+    pub file: String,
+    pub command: String,
+    pub cwd: String,
+    pub duration_ms: u128,
+    /// 退出码，超时或启动失败时为空
+    pub status: Option<i32>,
+    pub error: Option<String>,
+    pub output: String,
+}
+
+impl AuditEntry {
+    pub fn new(
+        action: &str,
+        file: &Path,
+        command: &str,
+        duration: Duration,
+        output: &Result<Output>,
+    ) -> AuditEntry {
+        let (status, error, output) = match output {
+            Ok(output) => {
+                let content = match output.status.success() {
+                    true => &output.stdout,
+                    false => &output.stderr,
+                };
+                (
+                    output.status.code(),
+                    None,
+                    truncate(&String::from_utf8_lossy(content), MAX_OUTPUT_CHARS),
+                )
+            }
+            Err(err) => (None, Some(format!("{err:#}")), String::new()),
+        };
+
+        AuditEntry {
+            time: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_default(),
+            action: action.to_owned(),
+            file: file.to_string_lossy().into_owned(),
+            command: command.to_owned(),
+            cwd: std::env::current_dir()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            duration_ms: duration.as_millis(),
+            status,
+            error,
+            output,
+        }
+    }
+}
+
+/// 写入审计日志，失败时只记录错误不影响 action 执行
+pub fn record(entry: &AuditEntry) {
+    if let Err(err) = append(&log_path(), entry) {
+        error!("action log write fail: {err:?}");
+    }
+}
+
+fn append(path: &Path, entry: &AuditEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &s[..idx]),
+        None => s.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path, time::Duration};
+
+    use super::{AuditEntry, append, truncate};
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("你好世界", 2), "你好…");
+    }
+
+    #[test]
+    fn test_append() {
+        let path =
+            std::env::temp_dir().join(format!("hx-lsp-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let entry = AuditEntry::new(
+            "run",
+            Path::new("/tmp/main.go"),
+            "go run main.go",
+            Duration::from_millis(12),
+            &Err(anyhow::anyhow!("Command timed out")),
+        );
+
+        append(&path, &entry).unwrap();
+        append(&path, &entry).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["action"], "run");
+        assert_eq!(value["duration_ms"], 12);
+        assert_eq!(value["status"], serde_json::Value::Null);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+// 各语言的注释符号
+// 参考 helix languages.toml
+
+/// 注释符号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentTokens {
+    pub line: Option<&'static str>,
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+const C_STYLE: CommentTokens = CommentTokens {
+    line: Some("//"),
+    block: Some(("/*", "*/")),
+};
+
+const HASH: CommentTokens = CommentTokens {
+    line: Some("#"),
+    block: None,
+};
+
+const HTML: CommentTokens = CommentTokens {
+    line: None,
+    block: Some(("<!--", "-->")),
+};
+
+const fn line(token: &'static str) -> CommentTokens {
+    CommentTokens {
+        line: Some(token),
+        block: None,
+    }
+}
+
+const fn both(token: &'static str, start: &'static str, end: &'static str) -> CommentTokens {
+    CommentTokens {
+        line: Some(token),
+        block: Some((start, end)),
+    }
+}
+
+/// 根据 language id 获取注释符号，兼容常见的代码块语言别名
+pub fn tokens(lang_id: &str) -> Option<CommentTokens> {
+    let tokens = match lang_id.to_ascii_lowercase().as_str() {
+        "rust" | "rs" | "c" | "h" | "cpp" | "c++" | "cc" | "c-sharp" | "csharp" | "cs" | "java"
+        | "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" | "go" | "golang"
+        | "kotlin" | "kt" | "swift" | "scala" | "dart" | "php" | "groovy" | "protobuf"
+        | "proto" | "jsonc" | "json5" | "scss" | "less" | "glsl" | "wgsl" | "hlsl" | "d"
+        | "solidity" | "objc" | "odin" | "v" | "typst" | "verilog" => C_STYLE,
+        "zig" | "gleam" | "fsharp" => line("//"),
+        "css" => CommentTokens {
+            line: None,
+            block: Some(("/*", "*/")),
+        },
+        "python" | "py" | "bash" | "sh" | "shell" | "zsh" | "fish" | "nu" | "toml" | "yaml"
+        | "yml" | "dockerfile" | "make" | "makefile" | "cmake" | "r" | "elixir" | "ex" | "perl"
+        | "pl" | "graphql" | "gitignore" | "git-config" | "env" | "just" | "nginx"
+        | "powershell" | "ps1" | "starlark" | "meson" | "gdscript" => HASH,
+        "ruby" | "rb" => both("#", "=begin", "=end"),
+        "nix" | "hcl" | "terraform" | "tf" => both("#", "/*", "*/"),
+        "julia" | "jl" => both("#", "#=", "=#"),
+        "lua" => both("--", "--[[", "]]"),
+        "haskell" | "hs" | "elm" | "purescript" | "idris" | "agda" => both("--", "{-", "-}"),
+        "sql" => both("--", "/*", "*/"),
+        "ocaml" | "ml" => CommentTokens {
+            line: None,
+            block: Some(("(*", "*)")),
+        },
+        "html" | "xml" | "svg" | "markdown" | "md" | "vue" | "svelte" | "astro" => HTML,
+        "clojure" | "clj" | "scheme" | "commonlisp" | "lisp" | "elisp" | "racket" | "fennel"
+        | "ini" => line(";"),
+        "erlang" | "erl" | "latex" | "tex" | "matlab" => line("%"),
+        "vim" | "viml" => line("\""),
+        "bat" | "cmd" => line("REM"),
+        _ => return None,
+    };
+
+    Some(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use super::tokens;
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(tokens("rust").unwrap().line, Some("//"));
+        assert_eq!(tokens("Python").unwrap().block, None);
+        assert_eq!(tokens("html").unwrap().block, Some(("<!--", "-->")));
+        assert_eq!(tokens("lua").unwrap().block, Some(("--[[", "]]")));
+        assert!(tokens("plaintext").is_none());
+    }
+}
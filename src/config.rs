@@ -1,15 +1,75 @@
 use serde::Deserialize;
 
+use crate::action_inner::CaseStyle;
+
 fn default_true() -> bool {
     true
 }
 
+fn default_tab_size() -> usize {
+    2
+}
+
+fn default_toc_min_level() -> u8 {
+    1
+}
+
+fn default_toc_max_level() -> u8 {
+    6
+}
+
+fn default_wrap_width() -> usize {
+    80
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct LspConfig {
     #[serde(default = "default_true")]
     pub markdown: bool,
     #[serde(default = "default_true")]
     pub document_color: bool,
+    /// 是否加载项目目录下的 `.helix/actions`
+    #[serde(default = "default_true")]
+    pub project_actions: bool,
+    /// 是否记录 action 命令审计日志
+    #[serde(default)]
+    pub action_log: bool,
+    /// 提供的单词风格转换
+    #[serde(default = "CaseStyle::all")]
+    pub case_actions: Vec<CaseStyle>,
+    /// 单词风格转换是否提供替换所有已打开文档的 action
+    #[serde(default)]
+    pub case_all_documents: bool,
+    /// 格式化缩进宽度
+    #[serde(default = "default_tab_size")]
+    pub tab_size: usize,
+    /// 格式化是否使用空格缩进
+    #[serde(default = "default_true")]
+    pub insert_spaces: bool,
+    /// 目录包含的最小标题级别
+    #[serde(default = "default_toc_min_level")]
+    pub toc_min_level: u8,
+    /// 目录包含的最大标题级别
+    #[serde(default = "default_toc_max_level")]
+    pub toc_max_level: u8,
+    /// 是否声明文档格式化能力，仅格式化 Markdown
+    #[serde(default)]
+    pub formatting: bool,
+    /// 格式化时对齐表格
+    #[serde(default = "default_true")]
+    pub format_tables: bool,
+    /// 格式化时统一列表标记与缩进
+    #[serde(default = "default_true")]
+    pub format_list_marker: bool,
+    /// 格式化时去除行尾空白
+    #[serde(default = "default_true")]
+    pub format_trailing_whitespace: bool,
+    /// 格式化时确保标题与代码块前后有空行
+    #[serde(default = "default_true")]
+    pub format_blank_lines: bool,
+    /// 段落重排宽度
+    #[serde(default = "default_wrap_width")]
+    pub wrap_width: usize,
 }
 
 impl Default for LspConfig {
@@ -17,6 +77,20 @@ impl Default for LspConfig {
         Self {
             markdown: true,
             document_color: true,
+            project_actions: true,
+            action_log: false,
+            case_actions: CaseStyle::all(),
+            case_all_documents: false,
+            tab_size: default_tab_size(),
+            insert_spaces: true,
+            toc_min_level: default_toc_min_level(),
+            toc_max_level: default_toc_max_level(),
+            formatting: false,
+            format_tables: true,
+            format_list_marker: true,
+            format_trailing_whitespace: true,
+            format_blank_lines: true,
+            wrap_width: default_wrap_width(),
         }
     }
 }
@@ -26,26 +100,73 @@ impl LspConfig {
         let mut config = LspConfig::default();
 
         if let Some(obj) = value.as_object() {
-            if let Some(settings) = obj.get("settings")
-                && let Some(settings_obj) = settings.as_object()
-            {
-                if let Some(v) = settings_obj.get("markdown").and_then(|v| v.as_bool()) {
-                    config.markdown = v;
-                }
-                if let Some(v) = settings_obj.get("documentColor").and_then(|v| v.as_bool()) {
-                    config.document_color = v;
-                }
-                return config;
-            }
-
-            if let Some(v) = obj.get("markdown").and_then(|v| v.as_bool()) {
-                config.markdown = v;
-            }
-            if let Some(v) = obj.get("documentColor").and_then(|v| v.as_bool()) {
-                config.document_color = v;
-            }
+            // 兼容 `{ "settings": { ... } }` 嵌套格式
+            let obj = obj
+                .get("settings")
+                .and_then(|settings| settings.as_object())
+                .unwrap_or(obj);
+            config.update(obj);
         }
 
         config
     }
+
+    fn update(&mut self, obj: &serde_json::Map<String, serde_json::Value>) {
+        if let Some(v) = obj.get("markdown").and_then(|v| v.as_bool()) {
+            self.markdown = v;
+        }
+        if let Some(v) = obj.get("documentColor").and_then(|v| v.as_bool()) {
+            self.document_color = v;
+        }
+        if let Some(v) = obj.get("projectActions").and_then(|v| v.as_bool()) {
+            self.project_actions = v;
+        }
+        if let Some(v) = obj.get("actionLog").and_then(|v| v.as_bool()) {
+            self.action_log = v;
+        }
+        if let Some(v) = obj
+            .get("caseActions")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        {
+            self.case_actions = v;
+        }
+        if let Some(v) = obj.get("caseAllDocuments").and_then(|v| v.as_bool()) {
+            self.case_all_documents = v;
+        }
+        if let Some(v) = obj.get("tabSize").and_then(|v| v.as_u64()) {
+            self.tab_size = v as usize;
+        }
+        if let Some(v) = obj.get("insertSpaces").and_then(|v| v.as_bool()) {
+            self.insert_spaces = v;
+        }
+        if let Some(v) = obj.get("tocMinLevel").and_then(|v| v.as_u64()) {
+            self.toc_min_level = v.clamp(1, 6) as u8;
+        }
+        if let Some(v) = obj.get("tocMaxLevel").and_then(|v| v.as_u64()) {
+            self.toc_max_level = v.clamp(1, 6) as u8;
+        }
+        // 最小级别大于最大级别时无法生成目录，以最大级别为准
+        self.toc_min_level = self.toc_min_level.min(self.toc_max_level);
+        if let Some(v) = obj.get("formatting").and_then(|v| v.as_bool()) {
+            self.formatting = v;
+        }
+        if let Some(v) = obj.get("formatTables").and_then(|v| v.as_bool()) {
+            self.format_tables = v;
+        }
+        if let Some(v) = obj.get("formatListMarker").and_then(|v| v.as_bool()) {
+            self.format_list_marker = v;
+        }
+        if let Some(v) = obj
+            .get("formatTrailingWhitespace")
+            .and_then(|v| v.as_bool())
+        {
+            self.format_trailing_whitespace = v;
+        }
+        if let Some(v) = obj.get("formatBlankLines").and_then(|v| v.as_bool()) {
+            self.format_blank_lines = v;
+        }
+        if let Some(v) = obj.get("wrapWidth").and_then(|v| v.as_u64()) {
+            self.wrap_width = v.max(1) as usize;
+        }
+    }
 }
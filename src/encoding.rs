@@ -47,6 +47,32 @@ pub fn lsp_pos_to_pos(
     .map_err(|_| Error::PositionOutOfBounds(pos.line, pos.character))
 }
 
+/// 将文档中的字符位置转换为 LSP Position
+pub fn char_to_lsp_pos(doc: &Rope, char_idx: usize) -> Position {
+    let line = doc.char_to_line(char_idx);
+    let line_start = doc.line_to_char(line);
+    let character = doc.line(line).char_to_utf16_cu(char_idx - line_start);
+    Position::new(line as u32, character as u32)
+}
+
+/// 选择区域覆盖的起止行，结束于行首时不包含该行
+pub fn selected_lines(range: &Range) -> (usize, usize) {
+    let end = match range.end.line > range.start.line && range.end.character == 0 {
+        true => range.end.line - 1,
+        false => range.end.line,
+    };
+    (range.start.line as usize, end as usize)
+}
+
+/// 整个文档的区域
+pub fn document_range(doc: &Rope) -> Range {
+    let last = doc.len_lines() - 1;
+    Range::new(
+        Position::new(0, 0),
+        Position::new(last as u32, doc.line(last).len_utf16_cu() as u32),
+    )
+}
+
 /// 增量变更文本
 pub fn apply_content_change(
     doc: &mut Rope,
@@ -104,7 +130,20 @@ pub fn is_field(line: &RopeSlice, line_character_pos: usize) -> bool {
 }
 
 pub fn get_current_word<'a>(line: &'a RopeSlice, line_character_pos: usize) -> Option<&'a str> {
-    if line_character_pos == 0 || line_character_pos > line.len_chars() {
+    if line_character_pos == 0 {
+        return None;
+    }
+
+    let (start, end) = get_current_word_range(line, line_character_pos)?;
+    line.slice(start..end).as_str()
+}
+
+/// 光标所在单词在行内的字符范围
+pub fn get_current_word_range(
+    line: &RopeSlice,
+    line_character_pos: usize,
+) -> Option<(usize, usize)> {
+    if line_character_pos > line.len_chars() {
         return None;
     }
 
@@ -123,11 +162,10 @@ pub fn get_current_word<'a>(line: &'a RopeSlice, line_character_pos: usize) -> O
         return None;
     }
 
-    line.slice(
-        line_character_pos.saturating_sub(offset_sub)
-            ..line_character_pos.saturating_add(offset_add),
-    )
-    .as_str()
+    Some((
+        line_character_pos.saturating_sub(offset_sub),
+        line_character_pos.saturating_add(offset_add),
+    ))
 }
 
 /// 获取内容
@@ -148,6 +186,36 @@ pub fn get_range_content<'a>(doc: &'a Rope, range: &Range) -> Option<RopeSlice<'
     Some(s)
 }
 
+/// 查找文档中完整匹配的单词，边界由 [`char_is_word`] 判断
+pub fn find_word_ranges(doc: &Rope, word: &str) -> Vec<Range> {
+    if word.is_empty() {
+        return Vec::new();
+    }
+
+    doc.lines()
+        .enumerate()
+        .flat_map(|(line_idx, line)| {
+            let text = line.to_string();
+            text.match_indices(word)
+                .filter(|&(start, _)| {
+                    let end = start + word.len();
+                    let before = text[..start].chars().next_back();
+                    let after = text[end..].chars().next();
+                    !before.is_some_and(char_is_word) && !after.is_some_and(char_is_word)
+                })
+                .map(|(start, _)| {
+                    let start_char = line.byte_to_char(start);
+                    let end_char = line.byte_to_char(start + word.len());
+                    Range::new(
+                        Position::new(line_idx as u32, line.char_to_utf16_cu(start_char) as u32),
+                        Position::new(line_idx as u32, line.char_to_utf16_cu(end_char) as u32),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
 #[inline]
 pub fn char_is_punctuation(ch: char) -> bool {
     use unicode_general_category::{GeneralCategory, get_general_category};
@@ -4,7 +4,9 @@
 
 pub mod action;
 pub mod action_inner;
+pub mod audit;
 pub mod colors;
+pub mod comment;
 pub mod config;
 pub mod encoding;
 pub mod env;
@@ -13,7 +15,10 @@ pub mod fuzzy;
 pub mod loader;
 pub mod markdown;
 pub mod parser;
+#[cfg(feature = "script")]
+pub mod script;
 pub mod serve;
 pub mod snippet;
 pub mod state;
+pub mod trust;
 pub mod variables;
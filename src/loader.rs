@@ -37,3 +37,25 @@ pub fn config_dir(d: Dirs) -> PathBuf {
     path.push(d.to_string());
     path
 }
+
+/// hx-lsp 数据目录，保存工作区信任记录等
+pub fn data_dir() -> PathBuf {
+    let strategy = match choose_base_strategy() {
+        Ok(s) => s,
+        Err(_) => return PathBuf::new(),
+    };
+    let mut path = strategy.data_dir();
+    path.push("hx-lsp");
+    path
+}
+
+/// hx-lsp 状态目录，保存日志等，不支持时回退到数据目录
+pub fn state_dir() -> PathBuf {
+    let strategy = match choose_base_strategy() {
+        Ok(s) => s,
+        Err(_) => return PathBuf::new(),
+    };
+    let mut path = strategy.state_dir().unwrap_or_else(|| strategy.data_dir());
+    path.push("hx-lsp");
+    path
+}
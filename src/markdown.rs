@@ -1,21 +1,192 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use async_lsp::lsp_types::{
-    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Position, Range, TextEdit,
-    WorkspaceEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Command, CompletionItem,
+    Diagnostic, DocumentSymbol, FoldingRange, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use comrak::{ComrakOptions, ExtensionOptions};
+use list::{
+    ListType, Renumber, convert_to_list, detect_list_type, indent_list_items, is_task_line,
+    renumber_list, toggle_task_state,
 };
-use list::{ListType, convert_to_list, detect_list_type, is_task_line, toggle_task_state};
 use ropey::Rope;
 
-use crate::encoding::get_range_content;
+use crate::{
+    config::LspConfig,
+    encoding::{document_range, get_range_content},
+};
+
+pub use footnote::Footnote;
 
+/// 将光标所在表格以 CSV 格式复制到剪贴板，参数为 CSV 内容
+pub const COPY_AS_CSV: &str = "hx-lsp.copyAsCsv";
+
+mod complete;
+mod csv;
+mod footnote;
+mod format;
+mod heading;
+mod links;
 mod list;
+mod outline;
+mod reflink;
 mod table;
+mod toc;
+mod wrap;
+
+/// 容器前缀在续行中的形式，保留引用标记，列表标记替换为空格
+fn continuation_prefix(prefix: &str) -> String {
+    prefix
+        .chars()
+        .map(|c| {
+            if c == '>' || c.is_whitespace() {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
+
+/// 解析文档结构使用的 comrak 选项，特定功能在此基础上调整
+fn get_comrak_options() -> ComrakOptions<'static> {
+    ComrakOptions {
+        extension: ExtensionOptions {
+            tasklist: true,
+            strikethrough: false,
+            tagfilter: false,
+            table: true,
+            autolink: false,
+            superscript: false,
+            header_ids: None,
+            footnotes: false,
+            description_lists: false,
+            front_matter_delimiter: Some("---".to_string()),
+            multiline_block_quotes: false,
+            alerts: false,
+            math_dollars: false,
+            math_code: false,
+            wikilinks_title_after_pipe: false,
+            wikilinks_title_before_pipe: false,
+            underline: false,
+            subscript: false,
+            spoiler: false,
+            greentext: false,
+            image_url_rewriter: None,
+            link_url_rewriter: None,
+        },
+        ..Default::default()
+    }
+}
+
+/// 在文档末尾追加定义，最后一个非空行不是定义时先插入空行
+fn append_block(doc: &Rope, block: &str, is_definition: impl Fn(usize) -> bool) -> TextEdit {
+    let text = doc.to_string();
+    let content = text.trim_end();
+    let last_line = content.lines().count().saturating_sub(1);
+
+    let mut prefix = String::from(&text[content.len()..]);
+    if !prefix.contains('\n') && !content.is_empty() {
+        prefix.push('\n');
+    }
+    if !content.is_empty() && !is_definition(last_line) && !prefix.contains("\n\n") {
+        prefix.push('\n');
+    }
+
+    let last = content.lines().last().unwrap_or_default();
+    let start = Position::new(last_line as u32, last.encode_utf16().count() as u32);
+    TextEdit::new(
+        Range::new(start, document_range(doc).end),
+        format!("{prefix}{block}"),
+    )
+}
+
+/// 格式化 Markdown 文档，`range` 为空时格式化整个文档
+pub(super) fn format(
+    lang_id: &str,
+    doc: &Rope,
+    range: Option<Range>,
+    config: &LspConfig,
+) -> Vec<TextEdit> {
+    if lang_id != "markdown" || !config.markdown {
+        return Vec::new();
+    }
+    format::format(doc, range, config)
+}
+
+/// Markdown 链接目标、锚点与引用标签补全，不在链接中时返回 `None`
+pub(super) fn completion(
+    lang_id: &str,
+    uri: &Url,
+    doc: &Rope,
+    pos: Position,
+    documents: &HashMap<Url, Rope>,
+    root: &Path,
+    config: &LspConfig,
+) -> Option<Vec<CompletionItem>> {
+    if lang_id != "markdown" || !config.markdown {
+        return None;
+    }
+    complete::completions(uri, doc, pos, documents, root)
+}
+
+/// Markdown 链接诊断，非 Markdown 文档返回 `None`，关闭 Markdown 功能时返回空列表以清除旧诊断
+pub(super) fn diagnostics(
+    lang_id: &str,
+    uri: &Url,
+    doc: &Rope,
+    documents: &HashMap<Url, Rope>,
+    root: &Path,
+    config: &LspConfig,
+) -> Option<Vec<Diagnostic>> {
+    if lang_id != "markdown" {
+        return None;
+    }
+    match config.markdown {
+        true => Some(links::diagnostics(uri, doc, documents, root)),
+        false => Some(Vec::new()),
+    }
+}
+
+/// Markdown 文档大纲
+pub(super) fn document_symbols(
+    lang_id: &str,
+    doc: &Rope,
+    config: &LspConfig,
+) -> Vec<DocumentSymbol> {
+    if lang_id != "markdown" || !config.markdown {
+        return Vec::new();
+    }
+    outline::document_symbols(doc)
+}
+
+/// Markdown 折叠区域
+pub(super) fn folding_ranges(lang_id: &str, doc: &Rope, config: &LspConfig) -> Vec<FoldingRange> {
+    if lang_id != "markdown" || !config.markdown {
+        return Vec::new();
+    }
+    outline::folding_ranges(doc)
+}
+
+/// 脚注操作，编辑在 resolve 时生成
+pub(super) fn footnote_actions(
+    lang_id: &str,
+    doc: &Rope,
+    params: &CodeActionParams,
+    config: &LspConfig,
+) -> Vec<(CodeAction, Footnote)> {
+    if lang_id != "markdown" || !config.markdown {
+        return Vec::new();
+    }
+    footnote::footnote_actions(doc, params.range)
+}
 
 pub(super) fn actions(
     lang_id: String,
     doc: &Rope,
     params: &CodeActionParams,
+    config: &LspConfig,
+    clipboard: Option<&str>,
 ) -> Vec<CodeActionOrCommand> {
     if lang_id != "markdown" {
         return Vec::new();
@@ -35,6 +206,10 @@ pub(super) fn actions(
             items.push(("Table Format", table::format(range_content, params.range)));
         }
 
+        if let Some(edit) = csv::csv_to_table(range_content, params.range) {
+            items.push(("Convert to Markdown Table", vec![edit]));
+        }
+
         match detected_type {
             list::DetectedListType::None => {
                 if let Some(edits) = convert_to_list(range_content, params.range, ListType::Ordered)
@@ -128,6 +303,50 @@ pub(super) fn actions(
         ));
     }
 
+    for (title, mode) in [
+        ("Renumber List", Renumber::Sequential),
+        ("Renumber List (All 1.)", Renumber::AllOne),
+    ] {
+        if let Some(edits) = renumber_list(doc, params.range, mode) {
+            items.push((title, edits));
+        }
+    }
+    for (title, indent) in [("Indent List Item", true), ("Outdent List Item", false)] {
+        if let Some(edits) = indent_list_items(doc, params.range, indent) {
+            items.push((title, edits));
+        }
+    }
+
+    items.extend(table::edit_actions(doc, params.range.start));
+
+    let csv = csv::table_to_csv(doc, params.range.start);
+    if let Some((edit, _)) = &csv {
+        items.push(("Convert to CSV", vec![edit.clone()]));
+    }
+    items.extend(heading::heading_actions(doc, params.range));
+    items.extend(wrap::wrap_actions(doc, params.range, config.wrap_width));
+
+    if let Some(edits) = reflink::to_reference(doc, params.range) {
+        items.push(("Convert to Reference Links", edits));
+    }
+    if let Some(edits) = reflink::to_inline(doc, params.range) {
+        items.push(("Convert to Inline Links", edits));
+    }
+    if let Some(edit) =
+        reflink::link_from_clipboard(params.range, &range_content.to_string(), clipboard)
+    {
+        items.push(("Make Link from Clipboard URL", vec![edit]));
+    }
+
+    if let Some((title, edit)) = toc::toc(
+        doc,
+        params.range.start,
+        config.toc_min_level,
+        config.toc_max_level,
+    ) {
+        items.push((title, vec![edit]));
+    }
+
     items
         .iter()
         .filter_map(|(item, edits)| {
@@ -148,5 +367,36 @@ pub(super) fn actions(
                 .into(),
             )
         })
+        .chain(csv.map(|(_, csv)| {
+            Command::new(
+                "Copy as CSV".to_string(),
+                COPY_AS_CSV.to_string(),
+                Some(vec![csv.into()]),
+            )
+            .into()
+        }))
         .collect()
 }
+
+/// 按从后往前的顺序应用编辑，供测试使用
+#[cfg(test)]
+fn apply_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut doc = Rope::from_str(text);
+    // 同一位置的插入按数组顺序出现在结果中
+    let mut edits: Vec<TextEdit> = edits.iter().rev().cloned().collect();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+
+    let to_char = |doc: &Rope, pos: Position| {
+        let line = doc.line(pos.line as usize);
+        doc.line_to_char(pos.line as usize) + line.utf16_cu_to_char(pos.character as usize)
+    };
+
+    for edit in edits {
+        let start = to_char(&doc, edit.range.start);
+        let end = to_char(&doc, edit.range.end);
+        doc.remove(start..end);
+        doc.insert(start, &edit.new_text);
+    }
+
+    doc.to_string()
+}
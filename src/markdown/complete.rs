@@ -0,0 +1,269 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use async_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Position, Range, TextEdit, Url,
+};
+use ropey::Rope;
+
+use super::{
+    links::{markdown_text, reference_labels, resolve_path},
+    toc::headings,
+};
+
+const IMAGE_EXTENSIONS: [&str; 9] = [
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "ico", "avif",
+];
+
+/// 光标所在的补全上下文，`start` 为待替换内容在行首前缀中的字节位置
+#[derive(Debug, PartialEq, Eq)]
+enum Context<'a> {
+    /// 链接或图片目标路径
+    Path {
+        dest: &'a str,
+        image: bool,
+        start: usize,
+    },
+    /// `#` 之后的标题锚点
+    Anchor { path: &'a str, start: usize },
+    /// `[text][` 之后的引用标签
+    Label { start: usize },
+}
+
+/// 由光标前的行内容判断补全上下文
+fn context(prefix: &str) -> Option<Context<'_>> {
+    let (dest_start, image) = if let Some(idx) = prefix.rfind("](") {
+        let open = prefix[..idx].rfind('[');
+        let image = open.is_some_and(|open| prefix[..open].ends_with('!'));
+        (Some(idx + 2), image)
+    } else {
+        (None, false)
+    };
+    // 引用定义 `[label]: dest`
+    let definition = {
+        let trimmed = prefix.trim_start();
+        (prefix.len() - trimmed.len() < 4 && trimmed.starts_with('['))
+            .then(|| trimmed.find("]:"))
+            .flatten()
+            .map(|idx| {
+                let after = prefix.len() - trimmed.len() + idx + 2;
+                after + (prefix[after..].len() - prefix[after..].trim_start().len())
+            })
+    };
+
+    if let Some(start) = dest_start.or(definition) {
+        let dest = prefix[start..]
+            .strip_prefix('<')
+            .unwrap_or(&prefix[start..]);
+        let start = prefix.len() - dest.len();
+        if !dest.contains([')', '>', ' ', '\t']) {
+            return Some(match dest.split_once('#') {
+                Some((path, anchor)) => Context::Anchor {
+                    path,
+                    start: prefix.len() - anchor.len(),
+                },
+                None => Context::Path { dest, image, start },
+            });
+        }
+    }
+
+    let open = prefix.rfind('[')?;
+    (prefix[..open].ends_with(']') && !prefix[open..].contains(']'))
+        .then_some(Context::Label { start: open + 1 })
+}
+
+fn item(
+    label: String,
+    kind: CompletionItemKind,
+    detail: Option<String>,
+    range: Range,
+    new_text: String,
+) -> CompletionItem {
+    CompletionItem {
+        label,
+        kind: Some(kind),
+        detail,
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, new_text))),
+        ..Default::default()
+    }
+}
+
+/// 链接目标路径、标题锚点与引用标签补全，不在链接中时返回 `None`
+pub(super) fn completions(
+    uri: &Url,
+    doc: &Rope,
+    pos: Position,
+    documents: &HashMap<Url, Rope>,
+    root: &Path,
+) -> Option<Vec<CompletionItem>> {
+    let line = doc.get_line(pos.line as usize)?;
+    let cursor = line.utf16_cu_to_char((pos.character as usize).min(line.len_utf16_cu()));
+    let prefix = line.slice(..cursor).to_string();
+    let context = context(&prefix)?;
+
+    let file = uri.to_file_path().ok();
+    let base = file.as_deref().and_then(Path::parent).unwrap_or(root);
+    let range = |start: usize| {
+        Range::new(
+            Position::new(pos.line, prefix[..start].encode_utf16().count() as u32),
+            pos,
+        )
+    };
+
+    let items = match context {
+        Context::Anchor { path, start } => {
+            let text = match path {
+                "" => doc.to_string(),
+                path => markdown_text(&resolve_path(path, base, root)?, documents)?,
+            };
+            headings(&text)
+                .into_iter()
+                .map(|h| {
+                    let slug = h.slug.clone();
+                    item(
+                        h.slug,
+                        CompletionItemKind::REFERENCE,
+                        Some(h.text),
+                        range(start),
+                        slug,
+                    )
+                })
+                .collect()
+        }
+        Context::Path { dest, image, start } => {
+            let (dir, partial) = match dest.rfind('/') {
+                Some(idx) => (&dest[..=idx], &dest[idx + 1..]),
+                None => ("", dest),
+            };
+            let dir = match dir {
+                "" => base.to_path_buf(),
+                dir => resolve_path(dir, base, root)?,
+            };
+            let mut entries: Vec<(String, bool)> = std::fs::read_dir(dir)
+                .ok()?
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    let name = entry.file_name().into_string().ok()?;
+                    let is_dir = entry.file_type().ok()?.is_dir();
+                    if name.starts_with('.') && !partial.starts_with('.') {
+                        return None;
+                    }
+                    let ext = Path::new(&name)
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .map(str::to_lowercase)
+                        .unwrap_or_default();
+                    (is_dir || !image || IMAGE_EXTENSIONS.contains(&ext.as_str()))
+                        .then_some((name, is_dir))
+                })
+                .collect();
+            entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+            let start = start + dest.len() - partial.len();
+            entries
+                .into_iter()
+                .map(|(name, is_dir)| {
+                    let (label, kind) = match is_dir {
+                        true => (format!("{name}/"), CompletionItemKind::FOLDER),
+                        false => (name, CompletionItemKind::FILE),
+                    };
+                    let new_text = label.replace(' ', "%20");
+                    item(label, kind, None, range(start), new_text)
+                })
+                .collect()
+        }
+        Context::Label { start } => {
+            let mut seen = HashSet::new();
+            reference_labels(&doc.to_string())
+                .into_iter()
+                .filter(|label| seen.insert(label.to_lowercase()))
+                .map(|label| {
+                    item(
+                        label.clone(),
+                        CompletionItemKind::REFERENCE,
+                        None,
+                        range(start),
+                        label,
+                    )
+                })
+                .collect()
+        }
+    };
+    Some(items)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use async_lsp::lsp_types::{Position, Url};
+    use ropey::Rope;
+
+    use super::{Context, completions, context};
+
+    #[test]
+    fn test_context() {
+        assert_eq!(
+            context("see [a](docs/gu"),
+            Some(Context::Path {
+                dest: "docs/gu",
+                image: false,
+                start: 8
+            })
+        );
+        assert_eq!(
+            context("![alt](<img"),
+            Some(Context::Path {
+                dest: "img",
+                image: true,
+                start: 8
+            })
+        );
+        assert_eq!(
+            context("[a](other.md#in"),
+            Some(Context::Anchor {
+                path: "other.md",
+                start: 13
+            })
+        );
+        assert_eq!(
+            context("[id]: #"),
+            Some(Context::Anchor { path: "", start: 7 })
+        );
+        assert_eq!(context("[text][la"), Some(Context::Label { start: 7 }));
+        assert_eq!(context("[a](b) and c"), None);
+        assert_eq!(context("[a] [b"), None);
+    }
+
+    #[test]
+    fn test_completions() {
+        let dir = std::env::temp_dir().join(format!("hx-lsp-complete-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("img")).unwrap();
+        std::fs::write(dir.join("img/logo.png"), "").unwrap();
+        std::fs::write(dir.join("img/notes.txt"), "").unwrap();
+        std::fs::write(dir.join("other.md"), "# Other Title\n").unwrap();
+
+        let uri = Url::from_file_path(dir.join("doc.md")).unwrap();
+        let complete = |text: &str, line: u32, character: u32| -> Vec<String> {
+            let doc = Rope::from_str(text);
+            let pos = Position::new(line, character);
+            completions(&uri, &doc, pos, &HashMap::new(), &dir)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|item| item.label)
+                .collect()
+        };
+
+        assert_eq!(complete("![x](img/", 0, 9), ["logo.png"]);
+        assert_eq!(complete("[x](img/", 0, 8), ["logo.png", "notes.txt"]);
+        assert_eq!(complete("# Hello World\n[x](#", 1, 5), ["hello-world"]);
+        assert_eq!(complete("[x](other.md#", 0, 13), ["other-title"]);
+        assert_eq!(
+            complete("[x][\n\n[Foo]: a\n[bar]: b\n", 0, 4),
+            ["Foo", "bar"]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
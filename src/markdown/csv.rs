@@ -0,0 +1,209 @@
+use async_lsp::lsp_types::{Position, Range, TextEdit};
+use ropey::{Rope, RopeSlice};
+
+use super::table::{render_cells, table_cells};
+
+/// 选择内容为 CSV 或 TSV 时转换为 Markdown 表格
+pub(super) fn csv_to_table(rope: RopeSlice, range: Range) -> Option<TextEdit> {
+    let text = rope.to_string();
+    if text.trim_start().starts_with('|') {
+        return None;
+    }
+
+    let mut rows = ['\t', ','].into_iter().find_map(|delimiter| {
+        let rows = parse(&text, delimiter)?;
+        let columns = rows[0].len();
+        (rows.len() > 1
+            && columns > 1
+            && rows.iter().all(|row| row.len() == columns)
+            && (delimiter == '\t' || !is_prose(&rows)))
+        .then_some(rows)
+    })?;
+
+    if !has_header(&rows) {
+        let header = (1..=rows[0].len()).map(|i| format!("Column {i}")).collect();
+        rows.insert(0, header);
+    }
+
+    // 单元格中的 `|` 与换行需要转义
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| cell.trim().replace('|', "\\|").replace('\n', "<br>"))
+                .collect()
+        })
+        .collect();
+
+    let mut new_text = render_cells(rows);
+    if text.ends_with('\n') {
+        new_text.push('\n');
+    }
+    Some(TextEdit::new(range, new_text))
+}
+
+/// 光标所在表格转换为 CSV，返回表格区域的编辑与不含容器前缀的 CSV 内容
+pub(super) fn table_to_csv(doc: &Rope, pos: Position) -> Option<(TextEdit, String)> {
+    let (range, prefix, rows) = table_cells(doc, pos)?;
+    let csv = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| quote(&cell.replace("\\|", "|")))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let new_text = csv.replace('\n', &format!("\n{prefix}"));
+    Some((TextEdit::new(range, new_text), csv))
+}
+
+/// 解析 CSV，支持引号包围的字段及其中的分隔符、换行与 `""` 转义
+fn parse(text: &str, delimiter: char) -> Option<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_end_matches(['\n', '\r']).chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            c if quoted => field.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    row.push(field);
+    rows.push(row);
+
+    Some(rows)
+}
+
+/// 首行均为非空且不重复的非数值单元格，且存在其余各行均为数值的列，或各列都不含数值时视为表头
+fn has_header(rows: &[Vec<String>]) -> bool {
+    let is_number = |s: &str| s.trim().replace(',', "").parse::<f64>().is_ok();
+    let (header, body) = rows.split_first().unwrap();
+
+    if header
+        .iter()
+        .any(|cell| cell.trim().is_empty() || is_number(cell))
+    {
+        return false;
+    }
+    let mut unique = header.clone();
+    unique.sort();
+    unique.dedup();
+    if unique.len() != header.len() {
+        return false;
+    }
+
+    let numeric = |col: usize| body.iter().all(|row| is_number(&row[col]));
+    let any_numeric = |col: usize| body.iter().any(|row| is_number(&row[col]));
+    (0..header.len()).any(numeric) || !(0..header.len()).any(any_numeric)
+}
+
+/// 逗号分隔的单元格以句末标点结尾或词数过多时视为普通文本
+fn is_prose(rows: &[Vec<String>]) -> bool {
+    rows.iter().flatten().any(|cell| {
+        let cell = cell.trim();
+        cell.ends_with(['.', '!', '?', '。', '！', '？']) || cell.split_whitespace().count() > 4
+    })
+}
+
+fn quote(cell: &str) -> String {
+    match cell.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{csv_to_table, has_header, parse, table_to_csv};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("a,\"b, c\",\"say \"\"hi\"\"\"\n1,\"x\ny\",3\n", ',').unwrap(),
+            [vec!["a", "b, c", "say \"hi\""], vec!["1", "x\ny", "3"]]
+        );
+        assert_eq!(parse("a\tb\n1\t2", '\t').unwrap(), [["a", "b"], ["1", "2"]]);
+        assert!(parse("a,\"b\n", ',').is_none());
+    }
+
+    #[test]
+    fn test_has_header() {
+        let rows = |v: &[&[&str]]| -> Vec<Vec<String>> {
+            v.iter()
+                .map(|r| r.iter().map(|s| s.to_string()).collect())
+                .collect()
+        };
+        assert!(has_header(&rows(&[&["name", "age"], &["a", "1"]])));
+        assert!(!has_header(&rows(&[&["a", "1"], &["b", "2"]])));
+        assert!(!has_header(&rows(&[&["x", "x"], &["b", "2"]])));
+        // 同列数值仅出现在部分行时不足以判断为表头
+        assert!(!has_header(&rows(&[&["a", "b"], &["c", "1"], &["d", "e"]])));
+        assert!(has_header(&rows(&[&["a", "b"], &["c", "d"]])));
+    }
+
+    #[test]
+    fn test_csv_to_table() {
+        let doc = Rope::from_str("name,desc\nfoo,\"a|b\"\n");
+        let range = Range::new(Position::new(0, 0), Position::new(2, 0));
+        let edit = csv_to_table(doc.slice(..), range).unwrap();
+        assert_eq!(
+            edit.new_text,
+            "| name | desc |\n| ---- | ---- |\n| foo  | a\\|b |\n"
+        );
+
+        let doc = Rope::from_str("1\t2\n3\t4");
+        let range = Range::new(Position::new(0, 0), Position::new(1, 4));
+        let edit = csv_to_table(doc.slice(..), range).unwrap();
+        assert!(edit.new_text.starts_with("| Column 1 | Column 2 |"));
+
+        let doc = Rope::from_str("just text\nmore text\n");
+        assert!(csv_to_table(doc.slice(..), range).is_none());
+
+        let doc = Rope::from_str("Well, that is fine.\nSure, we can do it.\n");
+        let range = Range::new(Position::new(0, 0), Position::new(2, 0));
+        assert!(csv_to_table(doc.slice(..), range).is_none());
+    }
+
+    #[test]
+    fn test_table_to_csv() {
+        let doc = Rope::from_str("| a | b \\| c |\n|---|---|\n| 1, 2 | x |\n");
+        let (edit, csv) = table_to_csv(&doc, Position::new(2, 1)).unwrap();
+        assert_eq!(edit.new_text, "a,b | c\n\"1, 2\",x");
+        assert_eq!(csv, edit.new_text);
+        assert_eq!(edit.range.start, Position::new(0, 0));
+        assert_eq!(edit.range.end, Position::new(2, 12));
+
+        // 引用中的表格保留续行前缀
+        let doc = Rope::from_str("> | a | b |\n> |---|---|\n> | 1 | 2 |\n");
+        let (edit, csv) = table_to_csv(&doc, Position::new(2, 3)).unwrap();
+        assert_eq!(edit.range.start, Position::new(0, 2));
+        assert_eq!(edit.new_text, "a,b\n> 1,2");
+        assert_eq!(csv, "a,b\n1,2");
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use async_lsp::lsp_types::{CodeAction, CodeActionKind, Position, Range, TextEdit};
+use comrak::{Arena, ComrakOptions, nodes::NodeValue, parse_document};
+use ropey::{Rope, RopeSlice};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    append_block, get_comrak_options,
+    links::{fenced_lines, footnote_definitions, normalize_label, sourcepos_range},
+};
+use crate::encoding::{document_range, get_range_content};
+
+/// 启用脚注扩展的 comrak 选项
+fn footnote_options() -> ComrakOptions<'static> {
+    let mut options = get_comrak_options();
+    options.extension.footnotes = true;
+    options
+}
+
+/// 文档中的脚注引用及其区域，按出现顺序排列
+fn references(doc: &Rope, text: &str) -> Vec<(String, Range)> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &footnote_options());
+
+    root.descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            match &data.value {
+                NodeValue::FootnoteReference(reference) => {
+                    Some((reference.name.clone(), sourcepos_range(doc, data.sourcepos)))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// 延迟到 resolve 时生成编辑的脚注操作
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum Footnote {
+    /// 在指定位置插入脚注引用
+    Insert(Position),
+    Renumber,
+}
+
+impl Footnote {
+    pub fn apply(&self, doc: &Rope) -> Option<Vec<TextEdit>> {
+        match self {
+            Footnote::Insert(pos) => Some(insert_footnote(doc, *pos)),
+            Footnote::Renumber => renumber_footnotes(doc),
+        }
+    }
+}
+
+/// 光标位于正文时提供插入脚注，文档中有脚注定义时提供重新编号
+///
+/// 只做逐行检查，编辑在 resolve 时生成。
+pub(super) fn footnote_actions(doc: &Rope, range: Range) -> Vec<(CodeAction, Footnote)> {
+    let text = doc.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    let line = range.start.line as usize;
+
+    let mut items = Vec::new();
+    if range.start.line == range.end.line && in_prose(&lines, line) {
+        // Helix 的光标是一个字符宽的选择，此时插入到光标前
+        let width = get_range_content(doc, &range).map_or(0, |content| content.len_chars());
+        let pos = match width <= 1 {
+            true => range.start,
+            false => range.end,
+        };
+        items.push(("Insert Footnote", Footnote::Insert(pos)));
+    }
+    if !footnote_definitions(&text).is_empty() {
+        items.push(("Renumber Footnotes", Footnote::Renumber));
+    }
+
+    items
+        .into_iter()
+        .map(|(title, footnote)| {
+            let action = CodeAction {
+                title: title.to_string(),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                ..Default::default()
+            };
+            (action, footnote)
+        })
+        .collect()
+}
+
+/// 行是否为可以插入脚注的正文，排除代码块、标题、定义与空行
+fn in_prose(lines: &[&str], line: usize) -> bool {
+    let Some(text) = lines.get(line) else {
+        return false;
+    };
+    let trimmed = text.trim_start();
+    let indent = text.len() - trimmed.len();
+    let is_underline = |l: &str| {
+        let l = l.trim();
+        !l.is_empty() && (l.chars().all(|c| c == '=') || l.chars().all(|c| c == '-'))
+    };
+    let is_delimiter_row = trimmed.contains('-')
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':') || c.is_whitespace());
+
+    !(trimmed.is_empty()
+        || indent >= 4
+        || fenced_lines(lines)[line]
+        || trimmed.starts_with('#')
+        || is_underline(text)
+        || is_delimiter_row
+        || lines.get(line + 1).is_some_and(|next| is_underline(next))
+        || (trimmed.starts_with('[') && trimmed.contains("]:")))
+}
+
+/// 在指定位置插入下一个可用编号的脚注，并在文档末尾添加定义
+fn insert_footnote(doc: &Rope, pos: Position) -> Vec<TextEdit> {
+    let text = doc.to_string();
+    let definitions = footnote_definitions(&text);
+    let next = references(doc, &text)
+        .iter()
+        .map(|(label, _)| label.as_str())
+        .chain(definitions.iter().map(|d| d.label.as_str()))
+        .filter_map(|label| label.parse::<usize>().ok())
+        .max()
+        .unwrap_or_default()
+        + 1;
+
+    vec![
+        TextEdit::new(Range::new(pos, pos), format!("[^{next}]")),
+        append_block(doc, &format!("[^{next}]: \n"), |line| {
+            definitions.last().is_some_and(|d| d.end_line == line)
+        }),
+    ]
+}
+
+/// 行首到行尾（不含换行符）的区域
+fn lines_range(doc: &Rope, start: usize, end: usize) -> Range {
+    let line = doc.line(end);
+    let len = line.len_chars() - line_ending_len(line);
+    Range::new(
+        Position::new(start as u32, 0),
+        Position::new(end as u32, line.char_to_utf16_cu(len) as u32),
+    )
+}
+
+fn line_ending_len(line: RopeSlice) -> usize {
+    let text = line.to_string();
+    text.len() - text.trim_end_matches(['\n', '\r']).len()
+}
+
+/// 按首次引用顺序重新编号数字脚注，调整定义顺序，并删除没有引用的定义
+fn renumber_footnotes(doc: &Rope) -> Option<Vec<TextEdit>> {
+    let text = doc.to_string();
+    let definitions = footnote_definitions(&text);
+    let references = references(doc, &text);
+
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    for (label, _) in &references {
+        let label = normalize_label(label);
+        if label.parse::<usize>().is_ok() && !numbers.contains_key(&label) {
+            numbers.insert(label, numbers.len() + 1);
+        }
+    }
+    let referenced: Vec<String> = references
+        .iter()
+        .map(|(label, _)| normalize_label(label))
+        .collect();
+
+    // 引用或列表中的定义无法逐行识别，此时重新编号会使引用与定义错位
+    let scanned: Vec<String> = definitions
+        .iter()
+        .map(|d| normalize_label(&d.label))
+        .collect();
+    if numbers.keys().any(|label| !scanned.contains(label)) {
+        return None;
+    }
+
+    let mut references: Vec<TextEdit> = references
+        .iter()
+        .filter_map(|(label, range)| {
+            let number = numbers.get(&normalize_label(label))?.to_string();
+            (number != *label).then(|| TextEdit::new(*range, format!("[^{number}]")))
+        })
+        .collect();
+    // 定义内容中的引用随定义一起删除或移动，避免编辑重叠
+    let mut take_references = |start: usize, end: usize| -> Vec<TextEdit> {
+        let (inner, outer) = std::mem::take(&mut references)
+            .into_iter()
+            .partition(|edit| (start..=end).contains(&(edit.range.start.line as usize)));
+        references = outer;
+        inner
+    };
+
+    let mut edits = Vec::new();
+    let mut numbered = Vec::new();
+    for definition in &definitions {
+        let label = normalize_label(&definition.label);
+        if let Some(number) = numbers.get(&label) {
+            numbered.push((*number, definition));
+            continue;
+        }
+        if referenced.contains(&label) {
+            continue;
+        }
+        // 删除定义及其后的空行
+        take_references(definition.line, definition.end_line);
+        let mut end = definition.end_line + 1;
+        if doc
+            .get_line(end)
+            .is_some_and(|l| l.chars().all(char::is_whitespace))
+            && end + 1 < doc.len_lines()
+        {
+            end += 1;
+        }
+        let end = match end < doc.len_lines() {
+            true => Position::new(end as u32, 0),
+            false => document_range(doc).end,
+        };
+        edits.push(TextEdit::new(
+            Range::new(Position::new(definition.line as u32, 0), end),
+            String::new(),
+        ));
+    }
+
+    // 数字脚注定义按新编号依次填入原有定义的位置
+    let mut sorted = numbered.clone();
+    sorted.sort_by_key(|(number, _)| *number);
+    for ((_, slot), (number, definition)) in numbered.iter().zip(sorted) {
+        if slot.line == definition.line && definition.label == number.to_string() {
+            continue;
+        }
+        let range = lines_range(doc, definition.line, definition.end_line);
+        let mut block = Rope::from(get_range_content(doc, &range)?);
+
+        // 在定义文本内替换标签与其中的引用，位置相对于定义首行
+        let mut inner = take_references(definition.line, definition.end_line);
+        for edit in &mut inner {
+            edit.range.start.line -= definition.line as u32;
+            edit.range.end.line -= definition.line as u32;
+        }
+        let start = block.line(0).to_string().find("[^").unwrap_or_default() as u32;
+        let end = start + definition.label.encode_utf16().count() as u32 + 3;
+        inner.push(TextEdit::new(
+            Range::new(Position::new(0, start), Position::new(0, end)),
+            format!("[^{number}]"),
+        ));
+        inner.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+        for edit in inner {
+            let to_char = |pos: Position| {
+                block.line_to_char(pos.line as usize)
+                    + block
+                        .line(pos.line as usize)
+                        .utf16_cu_to_char(pos.character as usize)
+            };
+            let (start, end) = (to_char(edit.range.start), to_char(edit.range.end));
+            block.remove(start..end);
+            block.insert(start, &edit.new_text);
+        }
+
+        edits.push(TextEdit::new(
+            lines_range(doc, slot.line, slot.end_line),
+            block.to_string(),
+        ));
+    }
+    edits.extend(references);
+
+    (!edits.is_empty()).then_some(edits)
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{Footnote, footnote_actions, insert_footnote, renumber_footnotes};
+    use crate::markdown::apply_edits;
+
+    #[test]
+    fn test_insert_footnote() {
+        let text = "Text here.\n\n[^2]: two\n";
+        let edits = insert_footnote(&Rope::from_str(text), Position::new(0, 4));
+        assert_eq!(
+            apply_edits(text, &edits),
+            "Text[^3] here.\n\n[^2]: two\n[^3]: \n"
+        );
+
+        let text = "Plain";
+        let edits = insert_footnote(&Rope::from_str(text), Position::new(0, 5));
+        assert_eq!(apply_edits(text, &edits), "Plain[^1]\n\n[^1]: \n");
+    }
+
+    #[test]
+    fn test_footnote_actions() {
+        let actions = |text: &str, range: Range| -> Vec<(String, Footnote)> {
+            footnote_actions(&Rope::from_str(text), range)
+                .into_iter()
+                .map(|(action, footnote)| (action.title, footnote))
+                .collect()
+        };
+        let range =
+            |line, start, end| Range::new(Position::new(line, start), Position::new(line, end));
+
+        // 单字符选择插入到光标前，较长选择插入到选择之后
+        let text = "Some text\n";
+        let items = actions(text, range(0, 2, 3));
+        assert!(matches!(items[..], [(_, Footnote::Insert(pos))] if pos == Position::new(0, 2)));
+        let items = actions(text, range(0, 0, 4));
+        assert!(matches!(items[..], [(_, Footnote::Insert(pos))] if pos == Position::new(0, 4)));
+
+        // 代码块、标题与定义中不提供插入
+        let text =
+            "# Title\n\n```\ncode\n```\n\nSetext\n---\n\n[^1]: note\n\n| a | b |\n| - | - |\n";
+        for line in [0, 1, 3, 4, 6, 7, 9, 12] {
+            let titles: Vec<String> = actions(text, range(line, 0, 1))
+                .into_iter()
+                .map(|(title, _)| title)
+                .collect();
+            assert_eq!(titles, ["Renumber Footnotes"], "line {line}");
+        }
+    }
+
+    #[test]
+    fn test_renumber_footnotes() {
+        let text = "a[^3] b[^note] c[^1] d[^3]\n\n[^1]: one\n[^3]: three\n    more\n\n[^note]: n\n[^7]: orphan\n\n[^x]: gone\n";
+        let edits = renumber_footnotes(&Rope::from_str(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "a[^1] b[^note] c[^2] d[^1]\n\n[^1]: three\n    more\n[^2]: one\n\n[^note]: n\n"
+        );
+
+        // 定义中的引用随定义一起移动
+        let text = "x[^2] y[^1]\n\n[^1]: see[^2]\n[^2]: two";
+        let edits = renumber_footnotes(&Rope::from_str(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "x[^1] y[^2]\n\n[^1]: two\n[^2]: see[^1]"
+        );
+        assert!(renumber_footnotes(&Rope::from_str("a[^1]\n\n[^1]: x\n")).is_none());
+
+        // 定义位于引用块中时不重新编号
+        let text = "a[^2] b[^1]\n\n> [^1]: one\n> [^2]: two\n";
+        assert!(renumber_footnotes(&Rope::from_str(text)).is_none());
+    }
+}
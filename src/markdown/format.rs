@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+use async_lsp::lsp_types::{Position, Range, TextEdit};
+use comrak::{Arena, nodes::NodeValue, parse_document};
+use ropey::Rope;
+
+use super::{get_comrak_options, list::normalize_lists, table::format_tables};
+use crate::{
+    config::LspConfig,
+    encoding::{document_range, selected_lines},
+};
+
+/// 格式化 Markdown 文档，`range` 为空时格式化整个文档
+///
+/// 各规则可通过配置单独关闭，结果以仅替换发生变化的行的编辑返回。
+pub(super) fn format(doc: &Rope, range: Option<Range>, config: &LspConfig) -> Vec<TextEdit> {
+    let lines = range.as_ref().map(selected_lines);
+    let in_range = |line: usize| lines.is_none_or(|(start, end)| (start..=end).contains(&line));
+
+    let original = doc.to_string();
+    let mut text = original.clone();
+
+    if config.format_list_marker {
+        text = normalize_lists(&text, &in_range);
+    }
+    if config.format_tables {
+        text = format_tables(&text, &in_range);
+    }
+    if config.format_trailing_whitespace {
+        text = trim_trailing_whitespace(&text, &in_range);
+    }
+    if config.format_blank_lines {
+        text = ensure_blank_lines(&text, &in_range);
+    }
+
+    match text == original {
+        true => Vec::new(),
+        false => vec![changed_lines(doc, &original, &text)],
+    }
+}
+
+/// 去除首尾相同的行，生成只覆盖变化部分的编辑
+fn changed_lines(doc: &Rope, original: &str, text: &str) -> TextEdit {
+    let old: Vec<&str> = original.split('\n').collect();
+    let new: Vec<&str> = text.split('\n').collect();
+    let common = old.len().min(new.len());
+
+    // 至少保留一行参与比较，避免编辑起点越过文档末尾
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take(common - 1)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(common - prefix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let start = Position::new(prefix as u32, 0);
+    match suffix {
+        0 => TextEdit::new(
+            Range::new(start, document_range(doc).end),
+            new[prefix..].join("\n"),
+        ),
+        _ => TextEdit::new(
+            Range::new(start, Position::new((old.len() - suffix) as u32, 0)),
+            new[prefix..new.len() - suffix]
+                .iter()
+                .map(|line| format!("{line}\n"))
+                .collect(),
+        ),
+    }
+}
+
+/// 代码块与 HTML 块所在的行，以及以硬换行结尾的行
+fn special_lines(text: &str) -> (HashSet<usize>, HashSet<usize>) {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &get_comrak_options());
+
+    let mut verbatim = HashSet::new();
+    let mut hard_breaks = HashSet::new();
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        match data.value {
+            NodeValue::CodeBlock(_) | NodeValue::HtmlBlock(_) => {
+                verbatim.extend(data.sourcepos.start.line - 1..data.sourcepos.end.line);
+            }
+            NodeValue::LineBreak => {
+                hard_breaks.insert(data.sourcepos.start.line - 1);
+            }
+            _ => {}
+        }
+    }
+
+    (verbatim, hard_breaks)
+}
+
+/// 去除行尾空白，保留两个空格的硬换行
+fn trim_trailing_whitespace(text: &str, in_range: &dyn Fn(usize) -> bool) -> String {
+    let (verbatim, hard_breaks) = special_lines(text);
+    let lines: Vec<&str> = text.split('\n').collect();
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            if !in_range(idx) || verbatim.contains(&idx) {
+                return line.to_string();
+            }
+            let (line, cr) = match line.strip_suffix('\r') {
+                Some(line) => (line, "\r"),
+                None => (*line, ""),
+            };
+            let trimmed = line.trim_end();
+            match hard_breaks.contains(&idx) && line.ends_with("  ") {
+                true => format!("{trimmed}  {cr}"),
+                false => format!("{trimmed}{cr}"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 确保顶层标题与代码块前后有空行
+fn ensure_blank_lines(text: &str, in_range: &dyn Fn(usize) -> bool) -> String {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &get_comrak_options());
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    let is_blank = |lines: &[String], idx: usize| lines[idx].trim().is_empty();
+
+    let mut inserts: Vec<usize> = Vec::new();
+    for node in root.children() {
+        let data = node.data.borrow();
+        let block = match &data.value {
+            NodeValue::Heading(_) => true,
+            NodeValue::CodeBlock(code) => code.fenced,
+            _ => false,
+        };
+        let (start, end) = (data.sourcepos.start.line - 1, data.sourcepos.end.line - 1);
+        if !block || !in_range(start) {
+            continue;
+        }
+        if start > 0 && !is_blank(&lines, start - 1) {
+            inserts.push(start);
+        }
+        if end + 1 < lines.len() && !is_blank(&lines, end + 1) {
+            inserts.push(end + 1);
+        }
+    }
+
+    inserts.sort_unstable();
+    inserts.dedup();
+    for idx in inserts.into_iter().rev() {
+        lines.insert(idx, String::new());
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{ensure_blank_lines, format, trim_trailing_whitespace};
+    use crate::{config::LspConfig, markdown::apply_edits};
+
+    #[test]
+    fn test_trim_trailing_whitespace() {
+        let text = "a  \nb \t\n\n```\ncode  \n```\n- item  \n- last  ";
+        assert_eq!(
+            trim_trailing_whitespace(text, &|_| true),
+            "a  \nb\n\n```\ncode  \n```\n- item\n- last"
+        );
+    }
+
+    #[test]
+    fn test_ensure_blank_lines() {
+        let text = "text\n# Title\nbody\n```rust\nlet a;\n```\nafter\n";
+        assert_eq!(
+            ensure_blank_lines(text, &|_| true),
+            "text\n\n# Title\n\nbody\n\n```rust\nlet a;\n```\n\nafter\n"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let text = "---\ntitle: x\n---\n# Title\n* a  \n* b\n\n|a|b|\n|-|-|\n";
+        let doc = Rope::from_str(text);
+        let edits = format(&doc, None, &LspConfig::default());
+        assert_eq!(
+            apply_edits(text, &edits),
+            "---\ntitle: x\n---\n\n# Title\n\n- a\n- b\n\n| a   | b   |\n| --- | --- |\n"
+        );
+
+        let config = LspConfig {
+            format_list_marker: false,
+            format_blank_lines: false,
+            ..Default::default()
+        };
+        let edits = format(&doc, None, &config);
+        assert_eq!(
+            apply_edits(text, &edits),
+            "---\ntitle: x\n---\n# Title\n* a\n* b\n\n| a   | b   |\n| --- | --- |\n"
+        );
+
+        // 范围格式化只替换选中的行
+        let range = Range::new(Position::new(4, 0), Position::new(5, 0));
+        let edits = format(&doc, Some(range), &LspConfig::default());
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range, range);
+        assert_eq!(
+            apply_edits(text, &edits),
+            "---\ntitle: x\n---\n# Title\n- a\n* b\n\n|a|b|\n|-|-|\n"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use async_lsp::lsp_types::{Position, Range, TextEdit};
+use ropey::Rope;
+use unicode_width::UnicodeWidthStr;
+
+use super::{
+    continuation_prefix,
+    toc::{Heading, headings},
+};
+use crate::encoding::selected_lines;
+
+/// 标题级别调整与风格转换
+pub(super) fn heading_actions(doc: &Rope, range: Range) -> Vec<(&'static str, Vec<TextEdit>)> {
+    let all = headings(&doc.to_string());
+    let (start, end) = selected_lines(&range);
+
+    let selected: Vec<usize> = all
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| h.start_line <= end && h.end_line >= start)
+        .map(|(i, _)| i)
+        .collect();
+    if selected.is_empty() {
+        return Vec::new();
+    }
+
+    // 包含子章节的标题
+    let mut targets: Vec<usize> = Vec::new();
+    for &i in &selected {
+        let level = all[i].level;
+        targets.push(i);
+        targets.extend((i + 1..all.len()).take_while(|&j| all[j].level > level));
+    }
+    targets.sort_unstable();
+    targets.dedup();
+    let targets: Vec<&Heading> = targets.iter().map(|&i| &all[i]).collect();
+
+    let mut items = Vec::new();
+
+    if targets.iter().all(|h| h.level > 1) {
+        let edits = targets
+            .iter()
+            .flat_map(|h| set_level(doc, h, h.level - 1))
+            .collect();
+        items.push(("Promote Heading", edits));
+    }
+    if targets.iter().all(|h| h.level < 6) {
+        let edits = targets
+            .iter()
+            .flat_map(|h| set_level(doc, h, h.level + 1))
+            .collect();
+        items.push(("Demote Heading", edits));
+    }
+
+    let selected: Vec<&Heading> = selected.iter().map(|&i| &all[i]).collect();
+    if selected.iter().any(|h| h.setext) {
+        let edits = selected
+            .iter()
+            .filter(|h| h.setext)
+            .flat_map(|h| to_atx(doc, h, h.level))
+            .collect();
+        items.push(("Convert to ATX Heading", edits));
+    }
+    if selected.iter().any(|h| !h.setext && h.level <= 2) {
+        let edits = selected
+            .iter()
+            .filter(|h| !h.setext && h.level <= 2)
+            .flat_map(|h| to_setext(doc, h))
+            .collect();
+        items.push(("Convert to Setext Heading", edits));
+    }
+
+    items
+}
+
+/// 修改标题级别，setext 标题超过二级时转换为 ATX
+fn set_level(doc: &Rope, heading: &Heading, level: u8) -> Vec<TextEdit> {
+    if !heading.setext {
+        let line = line_text(doc, heading.start_line);
+        let indent = heading.column;
+        let hashes = line[indent..].chars().take_while(|&c| c == '#').count();
+        let range = Range::new(
+            Position::new(heading.start_line as u32, indent as u32),
+            Position::new(heading.start_line as u32, (indent + hashes) as u32),
+        );
+        return vec![TextEdit::new(range, "#".repeat(level as usize))];
+    }
+
+    if level > 2 {
+        return to_atx(doc, heading, level);
+    }
+
+    let underline = line_text(doc, heading.end_line);
+    let marker = if level == 1 { "=" } else { "-" };
+    vec![TextEdit::new(
+        line_range(doc, heading.end_line),
+        underline.trim_end().replace(['=', '-'], marker),
+    )]
+}
+
+/// setext 标题转换为 ATX 标题，多行内容合并为一行，保留容器前缀
+fn to_atx(doc: &Rope, heading: &Heading, level: u8) -> Vec<TextEdit> {
+    let content: Vec<String> = (heading.start_line..heading.end_line)
+        .map(|idx| {
+            let line = line_text(doc, idx);
+            let content = match idx == heading.start_line {
+                true => &line[heading.column..],
+                false => strip_container(&line, heading.column),
+            };
+            content.trim().to_string()
+        })
+        .collect();
+    let range = Range::new(
+        Position::new(heading.start_line as u32, heading.column as u32),
+        line_range(doc, heading.end_line).end,
+    );
+    vec![TextEdit::new(
+        range,
+        format!("{} {}", "#".repeat(level as usize), content.join(" ")),
+    )]
+}
+
+/// ATX 标题转换为 setext 标题，下划线行使用相同的容器前缀
+fn to_setext(doc: &Rope, heading: &Heading) -> Vec<TextEdit> {
+    let line = line_text(doc, heading.start_line);
+    let (prefix, content) = line.split_at(heading.column);
+    let content = content.trim().trim_start_matches('#').trim();
+    // 去除可选的结尾 `#`
+    let content = match content.trim_end_matches('#') {
+        rest if rest.ends_with(' ') || rest.is_empty() => rest.trim_end(),
+        _ => content,
+    };
+    let marker = if heading.level == 1 { "=" } else { "-" };
+    let underline = marker.repeat(content.width().max(3));
+    let prefix = continuation_prefix(prefix);
+    let range = Range::new(
+        Position::new(heading.start_line as u32, heading.column as u32),
+        line_range(doc, heading.start_line).end,
+    );
+    vec![TextEdit::new(
+        range,
+        format!("{content}\n{prefix}{underline}"),
+    )]
+}
+
+/// 去除续行开头不超过 `width` 字节的引用标记与缩进
+fn strip_container(line: &str, width: usize) -> &str {
+    let len = line
+        .char_indices()
+        .take_while(|(i, c)| *i < width && (*c == '>' || c.is_whitespace()))
+        .count();
+    &line[len..]
+}
+
+fn line_text(doc: &Rope, idx: usize) -> String {
+    doc.line(idx)
+        .to_string()
+        .trim_end_matches(['\n', '\r'])
+        .to_string()
+}
+
+fn line_range(doc: &Rope, idx: usize) -> Range {
+    let line = doc.line(idx);
+    let len = line
+        .to_string()
+        .trim_end_matches(['\n', '\r'])
+        .chars()
+        .count();
+    Range::new(
+        Position::new(idx as u32, 0),
+        Position::new(idx as u32, line.char_to_utf16_cu(len) as u32),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::heading_actions;
+    use crate::markdown::apply_edits;
+
+    fn action(doc: &str, line: u32, title: &str) -> Option<String> {
+        let range = Range::new(Position::new(line, 0), Position::new(line, 1));
+        heading_actions(&Rope::from_str(doc), range)
+            .into_iter()
+            .find(|(t, _)| *t == title)
+            .map(|(_, edits)| apply_edits(doc, &edits))
+    }
+
+    #[test]
+    fn test_promote_demote_with_subsections() {
+        let doc = "# A\n## B\n### C\n```\n## code\n```\n## D\n";
+        assert_eq!(
+            action(doc, 1, "Demote Heading").unwrap(),
+            "# A\n### B\n#### C\n```\n## code\n```\n## D\n"
+        );
+        assert_eq!(
+            action(doc, 1, "Promote Heading").unwrap(),
+            "# A\n# B\n## C\n```\n## code\n```\n## D\n"
+        );
+        assert!(action(doc, 0, "Promote Heading").is_none());
+        assert!(action(doc, 3, "Demote Heading").is_none());
+
+        // 整行选择结束于下一行行首时不包含下一行
+        let doc = "## A\n## B\n";
+        let range = Range::new(Position::new(0, 0), Position::new(1, 0));
+        let (_, edits) = heading_actions(&Rope::from_str(doc), range)
+            .into_iter()
+            .find(|(t, _)| *t == "Demote Heading")
+            .unwrap();
+        assert_eq!(apply_edits(doc, &edits), "### A\n## B\n");
+    }
+
+    #[test]
+    fn test_setext() {
+        let doc = "Title\n=====\n\nSub\n---\n";
+        assert_eq!(
+            action(doc, 0, "Demote Heading").unwrap(),
+            "Title\n-----\n\n### Sub\n"
+        );
+        assert_eq!(
+            action(doc, 3, "Convert to ATX Heading").unwrap(),
+            "Title\n=====\n\n## Sub\n"
+        );
+        assert_eq!(
+            action(doc, 3, "Demote Heading").unwrap(),
+            "Title\n=====\n\n### Sub\n"
+        );
+        assert_eq!(
+            action("## 标题 ##\n", 0, "Convert to Setext Heading").unwrap(),
+            "标题\n----\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_headings() {
+        assert_eq!(
+            action("> ## Quote heading\n", 0, "Promote Heading").unwrap(),
+            "> # Quote heading\n"
+        );
+        assert_eq!(
+            action("- # List heading\n", 0, "Demote Heading").unwrap(),
+            "- ## List heading\n"
+        );
+        assert_eq!(
+            action("> ## Quote heading\n", 0, "Convert to Setext Heading").unwrap(),
+            "> Quote heading\n> -------------\n"
+        );
+        assert_eq!(
+            action("- # Item\n", 0, "Convert to Setext Heading").unwrap(),
+            "- Item\n  ====\n"
+        );
+        assert_eq!(
+            action("> Quote\n> text\n> ---\n", 0, "Convert to ATX Heading").unwrap(),
+            "> ## Quote text\n"
+        );
+    }
+}
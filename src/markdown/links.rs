@@ -0,0 +1,469 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use async_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Url};
+use comrak::{
+    Arena,
+    nodes::{NodeValue, Sourcepos},
+    parse_document,
+};
+use percent_encoding::percent_decode_str;
+use ropey::Rope;
+
+use super::{
+    get_comrak_options,
+    toc::{headings, slugify},
+};
+use crate::action_inner::html_unescape;
+
+const SOURCE: &str = "hx-lsp";
+
+/// 规范化引用标签：忽略大小写，合并空白
+pub(super) fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 字节位置转换为 LSP 位置，`line` 与 `column` 从 0 开始
+fn byte_position(doc: &Rope, line: usize, column: usize) -> Position {
+    if line >= doc.len_lines() {
+        return Position::new(line as u32, 0);
+    }
+    let slice = doc.line(line);
+    let char_idx = slice.byte_to_char(column.min(slice.len_bytes()));
+    Position::new(line as u32, slice.char_to_utf16_cu(char_idx) as u32)
+}
+
+pub(super) fn sourcepos_range(doc: &Rope, pos: Sourcepos) -> Range {
+    Range::new(
+        byte_position(doc, pos.start.line - 1, pos.start.column - 1),
+        byte_position(doc, pos.end.line - 1, pos.end.column),
+    )
+}
+
+fn warning(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::WARNING),
+        source: Some(SOURCE.to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// 引用定义 `[label]: dest "title"` 或脚注定义 `[^label]: text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Definition {
+    pub label: String,
+    /// 定义中的链接目标与标题原文，跨行时以空格连接；脚注为首行文本
+    pub dest: String,
+    /// 所在行，从 0 开始
+    pub line: usize,
+    /// 最后一行，包含目标、标题或脚注内容所在的后续行
+    pub end_line: usize,
+}
+
+/// 定义原文中链接地址之后的部分
+fn definition_rest(dest: &str) -> &str {
+    match dest.strip_prefix('<') {
+        Some(rest) => rest
+            .split_once('>')
+            .map(|(_, rest)| rest)
+            .unwrap_or_default(),
+        None => dest
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest)
+            .unwrap_or_default(),
+    }
+    .trim()
+}
+
+/// 各行是否位于围栏代码块内，包括围栏所在行
+pub(super) fn fenced_lines(lines: &[&str]) -> Vec<bool> {
+    let mut fence: Option<&str> = None;
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                return true;
+            }
+            if line.len() - trimmed.len() < 4
+                && let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m))
+            {
+                fence = Some(marker);
+                return true;
+            }
+            false
+        })
+        .collect()
+}
+
+/// 文档中的引用定义与脚注定义，跳过代码块，脚注标签以 `^` 开头
+fn definitions(text: &str) -> Vec<Definition> {
+    let lines: Vec<&str> = text.lines().collect();
+    let fenced = fenced_lines(&lines);
+    let mut definitions = Vec::new();
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        let trimmed = line.trim_start();
+        let start = idx;
+        idx += 1;
+        if fenced[start] || line.len() - trimmed.len() >= 4 {
+            continue;
+        }
+        let Some((label, rest)) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("]:"))
+        else {
+            continue;
+        };
+        let label = label.trim();
+        let footnote = label.starts_with('^');
+        let valid = match footnote {
+            true => label.len() > 1 && !label.contains(char::is_whitespace),
+            false => !label.is_empty() && !rest.starts_with(':'),
+        };
+        if !valid {
+            continue;
+        }
+
+        // 脚注的续行需要缩进，引用定义的目标与标题可以写在后续行
+        let mut dest = rest.trim().to_string();
+        while idx < lines.len() && !fenced[idx] {
+            let next = lines[idx];
+            let continues = match footnote {
+                true => next.starts_with("    ") || next.starts_with('\t'),
+                false if dest.is_empty() => !next.trim().is_empty(),
+                false => {
+                    definition_rest(&dest).is_empty() && next.trim().starts_with(['"', '\'', '('])
+                }
+            };
+            if !continues {
+                break;
+            }
+            if !footnote {
+                if !dest.is_empty() {
+                    dest.push(' ');
+                }
+                dest.push_str(next.trim());
+            }
+            idx += 1;
+        }
+
+        definitions.push(Definition {
+            label: label.to_string(),
+            dest,
+            line: start,
+            end_line: idx - 1,
+        });
+    }
+    definitions
+}
+
+/// 文档中的引用定义，跳过代码块与脚注定义
+pub(super) fn reference_definitions(text: &str) -> Vec<Definition> {
+    definitions(text)
+        .into_iter()
+        .filter(|d| !d.label.starts_with('^'))
+        .collect()
+}
+
+/// 文档中的脚注定义 `[^label]: text`，包括没有引用的定义，标签不含 `^`
+pub(super) fn footnote_definitions(text: &str) -> Vec<Definition> {
+    definitions(text)
+        .into_iter()
+        .filter_map(|mut d| {
+            d.label = d.label.strip_prefix('^')?.to_string();
+            Some(d)
+        })
+        .collect()
+}
+
+/// 与 comrak 相同地去除反斜杠转义并解析实体，使定义原文可与解析后的链接比较
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && next.is_ascii_punctuation() => {
+                out.push(*next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    html_unescape(&out).unwrap_or(out)
+}
+
+/// 定义中的链接地址，已解码转义与实体
+pub(super) fn definition_url(dest: &str) -> String {
+    let url = match dest.strip_prefix('<') {
+        Some(rest) => rest.split('>').next().unwrap_or_default(),
+        None => dest.split_whitespace().next().unwrap_or_default(),
+    };
+    unescape(url)
+}
+
+/// 定义中的链接标题，去除引号并解码转义与实体
+pub(super) fn definition_title(dest: &str) -> String {
+    let rest = definition_rest(dest);
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next_back()) {
+        (Some('"'), Some('"')) | (Some('\''), Some('\'')) | (Some('('), Some(')')) => {
+            unescape(chars.as_str())
+        }
+        _ => String::new(),
+    }
+}
+
+/// 文档中的引用定义标签，保持原样
+pub(super) fn reference_labels(text: &str) -> Vec<String> {
+    reference_definitions(text)
+        .into_iter()
+        .map(|definition| definition.label)
+        .collect()
+}
+
+/// 文本中未解析的完整引用 `[text][label]` 与折叠引用 `[label][]`，返回字节区间与标签
+fn reference_uses(text: &str) -> Vec<(usize, usize, String)> {
+    let mut uses = Vec::new();
+    let mut from = 0;
+    while let Some(open) = text[from..].find('[').map(|i| from + i) {
+        if text[..open].ends_with('\\') {
+            from = open + 1;
+            continue;
+        }
+        let Some(close) = text[open + 1..].find(']').map(|i| open + 1 + i) else {
+            break;
+        };
+        let inner = &text[open + 1..close];
+        if inner.contains('[') {
+            from = open + 1;
+            continue;
+        }
+        let rest = &text[close + 1..];
+        match rest
+            .strip_prefix('[')
+            .and_then(|r| r.find(']').map(|i| &r[..i]))
+        {
+            Some(label) if !label.contains('[') => {
+                let end = close + 1 + label.len() + 2;
+                let label = if label.trim().is_empty() {
+                    inner
+                } else {
+                    label
+                };
+                if !label.trim().is_empty() {
+                    uses.push((open, end, normalize_label(label)));
+                }
+                from = end;
+            }
+            _ => from = close + 1,
+        }
+    }
+    uses
+}
+
+/// 链接目标对应的本地文件，外部链接返回 `None`
+pub(super) fn resolve_path(path: &str, base: &Path, root: &Path) -> Option<PathBuf> {
+    if path.contains("://") || Url::parse(path).is_ok() {
+        return None;
+    }
+    let path = percent_decode_str(path).decode_utf8_lossy();
+    Some(match path.strip_prefix('/') {
+        Some(path) => root.join(path),
+        None => base.join(path.as_ref()),
+    })
+}
+
+/// Markdown 文件内容，优先使用已打开的文档，非 Markdown 文件返回 `None`
+pub(super) fn markdown_text(target: &Path, documents: &HashMap<Url, Rope>) -> Option<String> {
+    let ext = target.extension()?.to_str()?.to_lowercase();
+    if !matches!(ext.as_str(), "md" | "markdown" | "mdx") {
+        return None;
+    }
+    Url::from_file_path(target)
+        .ok()
+        .and_then(|uri| documents.get(&uri).map(Rope::to_string))
+        .or_else(|| std::fs::read_to_string(target).ok())
+}
+
+/// 目标文件的标题锚点，非 Markdown 文件返回 `None`
+fn anchors(
+    target: &Path,
+    documents: &HashMap<Url, Rope>,
+    cache: &mut HashMap<PathBuf, Option<HashSet<String>>>,
+) -> Option<HashSet<String>> {
+    cache
+        .entry(target.to_path_buf())
+        .or_insert_with(|| {
+            let text = markdown_text(target, documents)?;
+            Some(headings(&text).into_iter().map(|h| h.slug).collect())
+        })
+        .clone()
+}
+
+/// 检查链接与图片目标、锚点、引用标签及重复的标题锚点
+pub(super) fn diagnostics(
+    uri: &Url,
+    doc: &Rope,
+    documents: &HashMap<Url, Rope>,
+    root: &Path,
+) -> Vec<Diagnostic> {
+    let text = doc.to_string();
+    let arena = Arena::new();
+    let node = parse_document(&arena, &text, &get_comrak_options());
+
+    let file = uri.to_file_path().ok();
+    let base = file
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(root)
+        .to_path_buf();
+    let own_anchors: HashSet<String> = headings(&text).into_iter().map(|h| h.slug).collect();
+    let labels: HashSet<String> = reference_labels(&text)
+        .iter()
+        .map(|label| normalize_label(label))
+        .collect();
+    let mut cache = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for node in node.descendants() {
+        let data = node.data.borrow();
+        match &data.value {
+            NodeValue::Link(link) | NodeValue::Image(link) => {
+                let (path, fragment) = match link.url.split_once('#') {
+                    Some((path, fragment)) => (path, Some(fragment)),
+                    None => (link.url.as_str(), None),
+                };
+                let path = path.split('?').next().unwrap_or_default();
+                let range = sourcepos_range(doc, data.sourcepos);
+
+                let target_anchors = if path.is_empty() {
+                    Some(own_anchors.clone())
+                } else {
+                    let Some(target) = resolve_path(path, &base, root) else {
+                        continue;
+                    };
+                    if !target.exists() {
+                        diagnostics.push(warning(range, format!("File not found: {path}")));
+                        continue;
+                    }
+                    anchors(&target, documents, &mut cache)
+                };
+
+                if let (Some(fragment), Some(anchors)) = (fragment, target_anchors) {
+                    let fragment = percent_decode_str(fragment).decode_utf8_lossy();
+                    if !fragment.is_empty() && !anchors.contains(&fragment.to_lowercase()) {
+                        diagnostics.push(warning(
+                            range,
+                            format!("No heading matches anchor: #{fragment}"),
+                        ));
+                    }
+                }
+            }
+            NodeValue::Text(_) if data.sourcepos.start.line == data.sourcepos.end.line => {
+                let (line, column) = (
+                    data.sourcepos.start.line - 1,
+                    data.sourcepos.start.column - 1,
+                );
+                // 转义与实体会改变字面量长度，按源码文本计算偏移
+                let source = doc.line(line).to_string();
+                let Some(source) = source.get(column..data.sourcepos.end.column) else {
+                    continue;
+                };
+                for (start, end, label) in reference_uses(source) {
+                    if labels.contains(&label) {
+                        continue;
+                    }
+                    let range = Range::new(
+                        byte_position(doc, line, column + start),
+                        byte_position(doc, line, column + end),
+                    );
+                    diagnostics.push(warning(range, format!("Undefined reference: [{label}]")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for heading in headings(&text) {
+        let slug = slugify(&heading.text);
+        if !seen.insert(slug.clone()) {
+            let line = doc.line(heading.start_line).to_string();
+            let len = line.trim_end_matches(['\n', '\r']).len();
+            let range = Range::new(
+                byte_position(doc, heading.start_line, 0),
+                byte_position(doc, heading.start_line, len),
+            );
+            diagnostics.push(warning(range, format!("Duplicate heading anchor: #{slug}")));
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use async_lsp::lsp_types::Url;
+    use ropey::Rope;
+
+    use super::{diagnostics, reference_labels, reference_uses};
+
+    #[test]
+    fn test_reference_uses() {
+        assert_eq!(
+            reference_uses("a [b][C d] [e][] [f] [g]"),
+            [(2, 10, "c d".to_string()), (11, 16, "e".to_string())]
+        );
+        let labels = reference_labels("[A]: x\n```\n[b]: y\n```\n[^c]: note\n");
+        assert_eq!(labels, ["A"]);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let dir = std::env::temp_dir().join(format!("hx-lsp-links-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("other.md"), "# Other Title\n").unwrap();
+
+        let text = "# Intro\n\n\
+            [ok](other.md#other-title) [bad](other.md#nope) [gone](missing.png)\n\
+            [self](#intro) [web](https://example.com/x.md) [x][def] [y][undef]\n\n\
+            # Intro\n\n\
+            a \\* &amp; [z][esc] \\[w][skip]\n\n\
+            [def]: #intro-1\n";
+        let uri = Url::from_file_path(dir.join("doc.md")).unwrap();
+        let mut messages: Vec<(u32, u32, String)> =
+            diagnostics(&uri, &Rope::from_str(text), &HashMap::new(), &dir)
+                .into_iter()
+                .map(|d| (d.range.start.line, d.range.start.character, d.message))
+                .collect();
+        messages.sort();
+
+        assert_eq!(
+            messages,
+            [
+                (2, 27, "No heading matches anchor: #nope".to_string()),
+                (2, 48, "File not found: missing.png".to_string()),
+                (3, 56, "Undefined reference: [undef]".to_string()),
+                (5, 0, "Duplicate heading anchor: #intro".to_string()),
+                (7, 11, "Undefined reference: [esc]".to_string()),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,15 @@
 use std::collections::HashMap;
 
 use async_lsp::lsp_types::{Position, Range, TextEdit};
-use comrak::{Arena, ComrakOptions, ExtensionOptions, parse_document};
+use comrak::{
+    Arena,
+    nodes::{AstNode, ListType as NodeListType, NodeValue},
+    parse_document,
+};
 use ropey::{Rope, RopeSlice};
 
+use super::get_comrak_options;
+
 /// 列表转换类型枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
@@ -28,36 +34,6 @@ pub enum DetectedListType {
     TaskChecked,
 }
 
-fn get_comrak_options() -> ComrakOptions<'static> {
-    ComrakOptions {
-        extension: ExtensionOptions {
-            tasklist: true,
-            strikethrough: false,
-            tagfilter: false,
-            table: false,
-            autolink: false,
-            superscript: false,
-            header_ids: None,
-            footnotes: false,
-            description_lists: false,
-            front_matter_delimiter: None,
-            multiline_block_quotes: false,
-            alerts: false,
-            math_dollars: false,
-            math_code: false,
-            wikilinks_title_after_pipe: false,
-            wikilinks_title_before_pipe: false,
-            underline: false,
-            subscript: false,
-            spoiler: false,
-            greentext: false,
-            image_url_rewriter: None,
-            link_url_rewriter: None,
-        },
-        ..Default::default()
-    }
-}
-
 /// 检测当前列表类型（基于行首标识字符串匹配）
 /// 多行时验证所有非空行类型一致
 pub fn detect_list_type(rope: RopeSlice) -> DetectedListType {
@@ -440,6 +416,307 @@ pub fn toggle_task_state(rope: RopeSlice, range: Range) -> Option<Vec<TextEdit>>
     (!edits.is_empty()).then_some(edits)
 }
 
+/// 重新编号方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renumber {
+    /// 从列表起始序号递增
+    Sequential,
+    /// 全部使用 `1.`
+    AllOne,
+    /// 顶层列表保持起始序号，嵌套列表从 1 开始
+    Nested,
+}
+
+/// 列表项的位置信息，行号从 0 开始
+#[derive(Debug, Clone)]
+struct ListItem {
+    start_line: usize,
+    end_line: usize,
+    /// 列表标记所在的字节列
+    marker_column: usize,
+    padding: usize,
+    ordered: bool,
+    bullet_char: u8,
+    /// 在所属列表中的序号
+    index: usize,
+    /// 所属列表的起始序号
+    list_start: usize,
+    depth: usize,
+    parent: Option<usize>,
+}
+
+/// 解析文档中未嵌套在引用块等容器内的列表项，返回顶层列表的行范围与列表项
+fn parse_list_items(text: &str) -> (Vec<(usize, usize)>, Vec<ListItem>) {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &get_comrak_options());
+
+    let mut lists = Vec::new();
+    let mut items = Vec::new();
+    for node in root.children() {
+        if let NodeValue::List(_) = node.data.borrow().value {
+            let pos = node.data.borrow().sourcepos;
+            lists.push((pos.start.line - 1, pos.end.line - 1));
+            collect_items(node, 0, None, &mut items);
+        }
+    }
+
+    (lists, items)
+}
+
+fn collect_items<'a>(
+    list: &'a AstNode<'a>,
+    depth: usize,
+    parent: Option<usize>,
+    items: &mut Vec<ListItem>,
+) {
+    // 列表项中的 start 为自身序号，起始序号以列表节点为准
+    let list_start = match &list.data.borrow().value {
+        NodeValue::List(node_list) => node_list.start,
+        _ => return,
+    };
+
+    for (index, item) in list.children().enumerate() {
+        let data = item.data.borrow();
+        let NodeValue::Item(node_list) = &data.value else {
+            continue;
+        };
+        let current = items.len();
+        items.push(ListItem {
+            start_line: data.sourcepos.start.line - 1,
+            end_line: data.sourcepos.end.line - 1,
+            marker_column: data.sourcepos.start.column - 1,
+            padding: node_list.padding,
+            ordered: node_list.list_type == NodeListType::Ordered,
+            bullet_char: node_list.bullet_char,
+            index,
+            list_start,
+            depth,
+            parent,
+        });
+        drop(data);
+
+        for child in item.children() {
+            if let NodeValue::List(_) = child.data.borrow().value {
+                collect_items(child, depth + 1, Some(current), items);
+            }
+        }
+    }
+}
+
+/// 重新编号有序列表，保留 `.` 与 `)` 分隔符
+fn renumber_text(text: &str, mode: Renumber) -> String {
+    let (_, items) = parse_list_items(text);
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+
+    for item in items.iter().filter(|item| item.ordered) {
+        let number = match mode {
+            Renumber::AllOne => 1,
+            Renumber::Nested if item.depth > 0 => item.index + 1,
+            _ => item.list_start + item.index,
+        };
+        let line = &mut lines[item.start_line];
+        let digits = line[item.marker_column..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .count();
+        line.replace_range(
+            item.marker_column..item.marker_column + digits,
+            &number.to_string(),
+        );
+    }
+
+    lines.join("\n")
+}
+
+/// 光标所在的顶层列表的行范围
+fn list_region(lists: &[(usize, usize)], start: usize, end: usize) -> Option<(usize, usize)> {
+    lists
+        .iter()
+        .find(|&&(first, last)| first <= start && end <= last)
+        .copied()
+}
+
+/// 替换顶层列表的编辑
+fn region_edit(doc: &Rope, region: (usize, usize), new_text: String) -> Option<Vec<TextEdit>> {
+    let (first, last) = region;
+    let old: String = (first..=last)
+        .map(|idx| doc.line(idx).to_string())
+        .collect();
+    let old = old.trim_end_matches(['\n', '\r']);
+    if old == new_text {
+        return None;
+    }
+
+    let last_line = doc.line(last);
+    let len = last_line
+        .to_string()
+        .trim_end_matches(['\n', '\r'])
+        .chars()
+        .count();
+    let range = Range::new(
+        Position::new(first as u32, 0),
+        Position::new(last as u32, last_line.char_to_utf16_cu(len) as u32),
+    );
+    Some(vec![TextEdit::new(range, new_text)])
+}
+
+fn region_text(doc: &Rope, region: (usize, usize)) -> String {
+    let text: String = (region.0..=region.1)
+        .map(|idx| doc.line(idx).to_string())
+        .collect();
+    text.trim_end_matches(['\n', '\r']).to_string()
+}
+
+/// 统一无序列表标记为 `-`，嵌套列表项对齐到父列表项的内容列
+///
+/// `in_range` 用于限制处理的行。
+pub fn normalize_lists(text: &str, in_range: &dyn Fn(usize) -> bool) -> String {
+    let (_, items) = parse_list_items(text);
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+
+    // 列表项按先序排列，父列表项总在子列表项之前
+    let mut targets = vec![0usize; items.len()];
+    for (i, item) in items.iter().enumerate() {
+        targets[i] = match item.parent {
+            Some(parent) => targets[parent] + items[parent].padding,
+            None => 0,
+        };
+    }
+
+    // 每行的偏移由包含该行的最内层列表项决定
+    let mut shifts: HashMap<usize, isize> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let shift = targets[i] as isize - item.marker_column as isize;
+        for line in item.start_line..=item.end_line {
+            shifts.insert(line, shift);
+        }
+    }
+
+    for (&idx, &shift) in &shifts {
+        let line = &mut lines[idx];
+        if !in_range(idx) || line.trim().is_empty() {
+            continue;
+        }
+        if shift > 0 {
+            line.insert_str(0, &" ".repeat(shift as usize));
+        } else {
+            let spaces = line.chars().take_while(|&c| c == ' ').count();
+            line.replace_range(..spaces.min(shift.unsigned_abs()), "");
+        }
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        let line = &mut lines[item.start_line];
+        if item.ordered || item.bullet_char == b'-' || !in_range(item.start_line) {
+            continue;
+        }
+        if line[targets[i]..].starts_with(item.bullet_char as char) {
+            line.replace_range(targets[i]..targets[i] + 1, "-");
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// 将有序列表标记改为 1
+fn set_marker_number(line: &mut String, column: usize) {
+    let digits = line[column..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .count();
+    line.replace_range(column..column + digits, "1");
+}
+
+/// 重新编号光标所在的列表
+pub fn renumber_list(doc: &Rope, range: Range, mode: Renumber) -> Option<Vec<TextEdit>> {
+    let (lists, _) = parse_list_items(&doc.to_string());
+    let region = list_region(&lists, range.start.line as usize, range.end.line as usize)?;
+    let new_text = renumber_text(&region_text(doc, region), mode);
+    region_edit(doc, region, new_text)
+}
+
+/// 缩进或取消缩进选中的列表项，并重新编号所在列表
+pub fn indent_list_items(doc: &Rope, range: Range, indent: bool) -> Option<Vec<TextEdit>> {
+    let line = doc.get_line(range.start.line as usize)?.to_string();
+    if detect_line_list_type(line.trim_start()) == DetectedListType::None {
+        return None;
+    }
+
+    let (lists, items) = parse_list_items(&doc.to_string());
+    let (start, end) = (range.start.line as usize, range.end.line as usize);
+    let region = list_region(&lists, start, end)?;
+
+    // 祖先已被选中的列表项随祖先移动
+    let selected: Vec<usize> = (0..items.len())
+        .filter(|&i| (start..=end).contains(&items[i].start_line))
+        .filter(|&i| {
+            let mut parent = items[i].parent;
+            while let Some(p) = parent {
+                if (start..=end).contains(&items[p].start_line) {
+                    return false;
+                }
+                parent = items[p].parent;
+            }
+            true
+        })
+        .collect();
+
+    let mut lines: Vec<String> = region_text(doc, region)
+        .split('\n')
+        .map(str::to_string)
+        .collect();
+    let mut changed = false;
+
+    for &i in &selected {
+        let item = &items[i];
+        let lines_range = item.start_line - region.0..=item.end_line - region.0;
+        if indent {
+            // 成为上一个同级列表项的子项
+            if item.index == 0 {
+                continue;
+            }
+            let prev = (0..i)
+                .rev()
+                .find(|&j| items[j].parent == item.parent && items[j].index == item.index - 1)?;
+            let pad = " ".repeat(items[prev].padding);
+            // 以其他序号开始的有序列表不能打断段落，先改为 1 再重新编号
+            if item.ordered {
+                set_marker_number(&mut lines[item.start_line - region.0], item.marker_column);
+            }
+            for line in &mut lines[lines_range] {
+                if !line.trim().is_empty() {
+                    line.insert_str(0, &pad);
+                }
+            }
+        } else {
+            let Some(parent) = item.parent else {
+                continue;
+            };
+            let width = items[parent].padding;
+            // 后续同级列表项成为该项的子列表
+            if let Some(next) = items
+                .iter()
+                .find(|next| next.parent == item.parent && next.index == item.index + 1)
+                .filter(|next| next.ordered)
+            {
+                set_marker_number(&mut lines[next.start_line - region.0], next.marker_column);
+            }
+            for line in &mut lines[lines_range] {
+                let spaces = line.chars().take_while(|&c| c == ' ').count().min(width);
+                line.replace_range(..spaces, "");
+            }
+        }
+        changed = true;
+    }
+
+    if !changed {
+        return None;
+    }
+
+    let new_text = renumber_text(&lines.join("\n"), Renumber::Nested);
+    region_edit(doc, region, new_text)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -585,4 +862,58 @@ mod tests {
         assert_eq!(edits[0].new_text, "- [ ] ");
         assert_eq!(edits[1].new_text, "- [ ] ");
     }
+
+    #[test]
+    fn test_renumber_text() {
+        let text = "3. a\n5. b\n   1. c\n   7. d\n9. e";
+        assert_eq!(
+            renumber_text(text, Renumber::Sequential),
+            "3. a\n4. b\n   1. c\n   2. d\n5. e"
+        );
+        assert_eq!(
+            renumber_text(text, Renumber::AllOne),
+            "1. a\n1. b\n   1. c\n   1. d\n1. e"
+        );
+        assert_eq!(
+            renumber_text("1) a\n1) b", Renumber::Sequential),
+            "1) a\n2) b"
+        );
+    }
+
+    #[test]
+    fn test_renumber_list() {
+        let doc = rope_from_str("text\n\n1. a\n1. b\n1. c\n");
+        let edits = renumber_list(&doc, range(3, 0, 3, 1), Renumber::Sequential).unwrap();
+        assert_eq!(edits[0].range, range(2, 0, 4, 4));
+        assert_eq!(edits[0].new_text, "1. a\n2. b\n3. c");
+        assert!(renumber_list(&doc, range(0, 0, 0, 1), Renumber::Sequential).is_none());
+    }
+
+    #[test]
+    fn test_indent_list_items() {
+        let doc = rope_from_str("1. a\n2. b\n3. c\n");
+        let edits = indent_list_items(&doc, range(1, 0, 1, 1), true).unwrap();
+        assert_eq!(edits[0].new_text, "1. a\n   1. b\n2. c");
+
+        let doc = rope_from_str("1. a\n   1. b\n   2. c\n2. d\n");
+        let edits = indent_list_items(&doc, range(1, 3, 1, 4), false).unwrap();
+        assert_eq!(edits[0].new_text, "1. a\n2. b\n   1. c\n3. d");
+
+        assert!(indent_list_items(&doc, range(0, 0, 0, 1), true).is_none());
+        assert!(indent_list_items(&doc, range(0, 0, 0, 1), false).is_none());
+    }
+
+    #[test]
+    fn test_normalize_lists() {
+        let text = "* a\n    + b\n      continued\n * c\n\n```\n* code\n```";
+        assert_eq!(
+            normalize_lists(text, &|_| true),
+            "- a\n  - b\n    continued\n- c\n\n```\n* code\n```"
+        );
+        assert_eq!(
+            normalize_lists("1. a\n      - b", &|_| true),
+            "1. a\n   - b"
+        );
+        assert_eq!(normalize_lists("* a\n* b", &|line| line == 1), "* a\n- b");
+    }
 }
@@ -0,0 +1,292 @@
+use async_lsp::lsp_types::{
+    DocumentSymbol, FoldingRange, FoldingRangeKind, Position, Range, SymbolKind,
+};
+use comrak::{Arena, nodes::NodeValue, parse_document};
+use ropey::Rope;
+
+use super::{
+    get_comrak_options,
+    toc::{Heading, headings, inline_text},
+};
+
+/// 表格、代码块等非标题块
+struct Block {
+    name: String,
+    detail: &'static str,
+    kind: SymbolKind,
+    start_line: usize,
+    end_line: usize,
+}
+
+/// 文档中的表格与代码块
+fn blocks(text: &str) -> Vec<Block> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &get_comrak_options());
+
+    root.descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            let (start_line, end_line) =
+                (data.sourcepos.start.line - 1, data.sourcepos.end.line - 1);
+            let (name, detail, kind) = match &data.value {
+                NodeValue::Table(_) => {
+                    let header = node
+                        .first_child()
+                        .map(|row| {
+                            row.children()
+                                .map(|cell| inline_text(cell).trim().to_string())
+                                .collect::<Vec<_>>()
+                                .join(" | ")
+                        })
+                        .unwrap_or_default();
+                    (header, "table", SymbolKind::ARRAY)
+                }
+                NodeValue::CodeBlock(code) if code.fenced => {
+                    let lang = code.info.split_whitespace().next().unwrap_or_default();
+                    let name = match lang {
+                        "" => "```".to_string(),
+                        lang => format!("```{lang}"),
+                    };
+                    (name, "code block", SymbolKind::OBJECT)
+                }
+                _ => return None,
+            };
+            Some(Block {
+                name,
+                detail,
+                kind,
+                start_line,
+                end_line,
+            })
+        })
+        .collect()
+}
+
+/// 各标题所在章节的结束行，不包含末尾空行
+fn section_ends(doc: &Rope, headings: &[Heading]) -> Vec<usize> {
+    let last_line = doc.len_lines().saturating_sub(1);
+    headings
+        .iter()
+        .enumerate()
+        .map(|(idx, heading)| {
+            let mut end = headings[idx + 1..]
+                .iter()
+                .find(|h| h.level <= heading.level)
+                .map_or(last_line, |h| h.start_line.saturating_sub(1));
+            while end > heading.end_line && doc.line(end).chars().all(char::is_whitespace) {
+                end -= 1;
+            }
+            end
+        })
+        .collect()
+}
+
+/// 行区域，结束于末行行尾
+fn line_range(doc: &Rope, start_line: usize, end_line: usize) -> Range {
+    let line = doc.line(end_line).to_string();
+    let line = line.trim_end_matches(['\n', '\r']);
+    Range::new(
+        Position::new(start_line as u32, 0),
+        Position::new(end_line as u32, line.encode_utf16().count() as u32),
+    )
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: String,
+    detail: &str,
+    kind: SymbolKind,
+    range: Range,
+    selection_range: Range,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: match name.is_empty() {
+            true => " ".to_string(),
+            false => name,
+        },
+        detail: Some(detail.to_string()),
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children: None,
+    }
+}
+
+/// 文档大纲：标题按级别嵌套，表格与代码块作为所在章节的子项
+pub(super) fn document_symbols(doc: &Rope) -> Vec<DocumentSymbol> {
+    let text = doc.to_string();
+    let headings = headings(&text);
+    let ends = section_ends(doc, &headings);
+
+    enum Entry {
+        Heading(usize),
+        Block(Block),
+    }
+    let mut entries: Vec<(usize, Entry)> = headings
+        .iter()
+        .enumerate()
+        .map(|(idx, h)| (h.start_line, Entry::Heading(idx)))
+        .chain(
+            blocks(&text)
+                .into_iter()
+                .map(|b| (b.start_line, Entry::Block(b))),
+        )
+        .collect();
+    entries.sort_by_key(|(line, _)| *line);
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<(u8, DocumentSymbol)> = Vec::new();
+
+    fn attach(
+        stack: &mut [(u8, DocumentSymbol)],
+        roots: &mut Vec<DocumentSymbol>,
+        s: DocumentSymbol,
+    ) {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(s),
+            None => roots.push(s),
+        }
+    }
+
+    for (_, entry) in entries {
+        match entry {
+            Entry::Heading(idx) => {
+                let heading = &headings[idx];
+                while stack
+                    .last()
+                    .is_some_and(|(level, _)| *level >= heading.level)
+                {
+                    let (_, done) = stack.pop().unwrap();
+                    attach(&mut stack, &mut roots, done);
+                }
+                let symbol = symbol(
+                    heading.text.clone(),
+                    &format!("h{}", heading.level),
+                    SymbolKind::STRING,
+                    line_range(doc, heading.start_line, ends[idx]),
+                    line_range(doc, heading.start_line, heading.end_line),
+                );
+                stack.push((heading.level, symbol));
+            }
+            Entry::Block(block) => {
+                let range = line_range(doc, block.start_line, block.end_line);
+                let symbol = symbol(block.name, block.detail, block.kind, range, range);
+                attach(&mut stack, &mut roots, symbol);
+            }
+        }
+    }
+    while let Some((_, done)) = stack.pop() {
+        attach(&mut stack, &mut roots, done);
+    }
+    roots
+}
+
+/// 折叠区域：章节、列表、代码块与 front matter
+pub(super) fn folding_ranges(doc: &Rope) -> Vec<FoldingRange> {
+    let text = doc.to_string();
+    let headings = headings(&text);
+    let ends = section_ends(doc, &headings);
+
+    let fold = |start: usize, end: usize, kind: Option<FoldingRangeKind>| FoldingRange {
+        start_line: start as u32,
+        end_line: end as u32,
+        kind,
+        ..Default::default()
+    };
+
+    let mut ranges: Vec<FoldingRange> = headings
+        .iter()
+        .zip(ends)
+        .filter(|(heading, end)| *end > heading.start_line)
+        .map(|(heading, end)| fold(heading.start_line, end, Some(FoldingRangeKind::Region)))
+        .collect();
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, &text, &get_comrak_options());
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let (start, mut end) = (data.sourcepos.start.line - 1, data.sourcepos.end.line - 1);
+        let kind = match &data.value {
+            NodeValue::List(_) => None,
+            NodeValue::CodeBlock(code) if code.fenced => None,
+            NodeValue::FrontMatter(content) => {
+                // front matter 内容包含其后的空行
+                end = start + content.trim_end().lines().count().saturating_sub(1);
+                Some(FoldingRangeKind::Comment)
+            }
+            _ => continue,
+        };
+        while end > start && doc.line(end).chars().all(char::is_whitespace) {
+            end -= 1;
+        }
+        if end > start {
+            ranges.push(fold(start, end, kind));
+        }
+    }
+
+    ranges.sort_by_key(|r| (r.start_line, std::cmp::Reverse(r.end_line)));
+    ranges
+}
+
+#[cfg(test)]
+mod test {
+    use ropey::Rope;
+
+    use super::{document_symbols, folding_ranges};
+
+    const TEXT: &str = "---
+title: test
+---
+
+# One
+
+| a | b |
+| - | - |
+| 1 | 2 |
+
+## Two
+
+```rust
+fn main() {}
+```
+
+- a
+- b
+
+# Three
+text
+";
+
+    #[test]
+    fn test_document_symbols() {
+        let symbols = document_symbols(&Rope::from_str(TEXT));
+        assert_eq!(symbols.len(), 2);
+
+        let one = &symbols[0];
+        assert_eq!(one.name, "One");
+        assert_eq!((one.range.start.line, one.range.end.line), (4, 17));
+        let children = one.children.as_ref().unwrap();
+        assert_eq!(children[0].name, "a | b");
+        assert_eq!(children[1].name, "Two");
+        let code = &children[1].children.as_ref().unwrap()[0];
+        assert_eq!(code.name, "```rust");
+        assert_eq!((code.range.start.line, code.range.end.line), (12, 14));
+
+        assert_eq!(symbols[1].name, "Three");
+        assert!(symbols[1].children.is_none());
+    }
+
+    #[test]
+    fn test_folding_ranges() {
+        let ranges: Vec<(u32, u32)> = folding_ranges(&Rope::from_str(TEXT))
+            .iter()
+            .map(|r| (r.start_line, r.end_line))
+            .collect();
+        assert_eq!(
+            ranges,
+            [(0, 2), (4, 17), (10, 17), (12, 14), (16, 17), (19, 20)]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use async_lsp::lsp_types::{Position, Range, TextEdit, Url};
+use comrak::{Arena, nodes::NodeValue, parse_document};
+use ropey::Rope;
+
+use super::{
+    append_block, get_comrak_options,
+    links::{
+        definition_title, definition_url, normalize_label, reference_definitions, sourcepos_range,
+    },
+};
+use crate::encoding::document_range;
+
+/// 链接或图片的源码
+struct LinkSource {
+    range: Range,
+    source: String,
+    url: String,
+    title: String,
+    /// 是否位于另一个链接内，如徽章 `[![CI](ci.svg)](ci.com)` 中的图片
+    nested: bool,
+}
+
+/// 文档中的链接与图片，跳过自动链接
+fn link_sources(doc: &Rope, text: &str) -> Vec<LinkSource> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &get_comrak_options());
+
+    root.descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            let (NodeValue::Link(link) | NodeValue::Image(link)) = &data.value else {
+                return None;
+            };
+            let pos = data.sourcepos;
+            let start = doc.line_to_byte(pos.start.line - 1) + pos.start.column - 1;
+            let end = doc.line_to_byte(pos.end.line - 1) + pos.end.column;
+            let source = text.get(start..end)?.to_string();
+            let nested = node.ancestors().skip(1).any(|parent| {
+                matches!(
+                    parent.data.borrow().value,
+                    NodeValue::Link(_) | NodeValue::Image(_)
+                )
+            });
+            (source.starts_with('[') || source.starts_with("![")).then(|| LinkSource {
+                range: sourcepos_range(doc, pos),
+                source,
+                url: link.url.clone(),
+                title: link.title.clone(),
+                nested,
+            })
+        })
+        .collect()
+}
+
+/// 链接是否在选择区域内，未选择时判断是否包含光标
+fn selected(link: Range, selection: Range) -> bool {
+    if selection.start == selection.end {
+        link.start <= selection.start && selection.start <= link.end
+    } else {
+        link.start < selection.end && selection.start < link.end
+    }
+}
+
+/// 链接目标，包含空格时使用尖括号
+fn destination(url: &str, title: &str) -> String {
+    let url = match url.is_empty() || url.contains(' ') {
+        true => format!("<{url}>"),
+        false => url.to_string(),
+    };
+    match title.is_empty() {
+        true => url,
+        false => format!("{url} \"{}\"", title.replace('"', "\\\"")),
+    }
+}
+
+/// 将所选行内链接转换为引用链接，地址与标题相同的链接共用一个定义，新定义追加到文档末尾
+///
+/// 只转换最外层的链接，徽章中的图片保持原样。
+pub(super) fn to_reference(doc: &Rope, selection: Range) -> Option<Vec<TextEdit>> {
+    let text = doc.to_string();
+    let definitions = reference_definitions(&text);
+
+    let mut labels: HashMap<(String, String), String> = definitions
+        .iter()
+        .map(|d| {
+            let key = (
+                definition_url(&d.dest).to_string(),
+                definition_title(&d.dest),
+            );
+            (key, d.label.clone())
+        })
+        .collect();
+    let mut next = definitions
+        .iter()
+        .filter_map(|d| d.label.parse::<usize>().ok())
+        .max()
+        .unwrap_or_default()
+        + 1;
+
+    let mut edits = Vec::new();
+    let mut appended = String::new();
+    for link in link_sources(doc, &text) {
+        // 嵌套的链接保留在外层链接的文本中，避免编辑重叠
+        if link.nested || !link.source.ends_with(')') || !selected(link.range, selection) {
+            continue;
+        }
+        let Some(idx) = link.source.rfind("](") else {
+            continue;
+        };
+        let key = (link.url.clone(), link.title.clone());
+        let label = labels.entry(key).or_insert_with(|| {
+            let label = next.to_string();
+            next += 1;
+            appended.push_str(&format!(
+                "[{label}]: {}\n",
+                destination(&link.url, &link.title)
+            ));
+            label
+        });
+        edits.push(TextEdit::new(
+            link.range,
+            format!("{}[{label}]", &link.source[..=idx]),
+        ));
+    }
+    if edits.is_empty() {
+        return None;
+    }
+
+    if !appended.is_empty() {
+        edits.push(append_block(doc, &appended, |line| {
+            definitions.last().is_some_and(|d| d.end_line == line)
+        }));
+    }
+    Some(edits)
+}
+
+/// 链接文本部分与引用标签
+fn reference_parts(source: &str) -> (&str, &str) {
+    fn inner(text: &str) -> &str {
+        let text = text.strip_prefix('!').unwrap_or(text);
+        let text = text.strip_prefix('[').unwrap_or(text);
+        text.strip_suffix(']').unwrap_or(text)
+    }
+    if let Some(text) = source.strip_suffix("[]") {
+        (text, inner(text))
+    } else if let Some(idx) = source.rfind("][") {
+        (&source[..=idx], &source[idx + 2..source.len() - 1])
+    } else {
+        (source, inner(source))
+    }
+}
+
+/// 将所选最外层的引用链接转换为行内链接，并删除不再使用的定义
+pub(super) fn to_inline(doc: &Rope, selection: Range) -> Option<Vec<TextEdit>> {
+    let text = doc.to_string();
+    let definitions: HashMap<String, _> = reference_definitions(&text)
+        .into_iter()
+        .map(|d| (normalize_label(&d.label), d))
+        .collect();
+
+    let mut remaining: HashMap<String, usize> = HashMap::new();
+    let mut edits = Vec::new();
+    for link in link_sources(doc, &text) {
+        if link.source.ends_with(')') {
+            continue;
+        }
+        let (text_part, label) = reference_parts(&link.source);
+        let label = normalize_label(label);
+        if link.nested || !selected(link.range, selection) {
+            *remaining.entry(label).or_default() += 1;
+            continue;
+        }
+        remaining.entry(label.clone()).or_default();
+        let dest = match definitions.get(&label) {
+            Some(definition) => definition.dest.clone(),
+            None => destination(&link.url, &link.title),
+        };
+        edits.push(TextEdit::new(link.range, format!("{text_part}({dest})")));
+    }
+    if edits.is_empty() {
+        return None;
+    }
+
+    for (label, count) in remaining {
+        let Some(definition) = definitions.get(&label).filter(|_| count == 0) else {
+            continue;
+        };
+        let end = match definition.end_line + 1 < doc.len_lines() {
+            true => Position::new(definition.end_line as u32 + 1, 0),
+            false => document_range(doc).end,
+        };
+        edits.push(TextEdit::new(
+            Range::new(Position::new(definition.line as u32, 0), end),
+            String::new(),
+        ));
+    }
+    Some(edits)
+}
+
+/// 以剪贴板中的 URL 为目标，将所选文本转换为链接
+pub(super) fn link_from_clipboard(
+    selection: Range,
+    content: &str,
+    clipboard: Option<&str>,
+) -> Option<TextEdit> {
+    let url = clipboard?.trim();
+    if content.is_empty() || content.contains('\n') || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let parsed = Url::parse(url).ok()?;
+    (parsed.has_host() || parsed.scheme() == "mailto")
+        .then(|| TextEdit::new(selection, format!("[{content}]({url})")))
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{link_from_clipboard, to_inline, to_reference};
+    use crate::markdown::apply_edits;
+
+    fn all(text: &str) -> Range {
+        let lines = text.lines().count() as u32;
+        Range::new(Position::new(0, 0), Position::new(lines, 0))
+    }
+
+    #[test]
+    fn test_to_reference() {
+        let text = "See [a](https://a.com) and ![b](b.png \"B\").\nAgain [a2](https://a.com).\n";
+        let edits = to_reference(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "See [a][1] and ![b][2].\nAgain [a2][1].\n\n[1]: https://a.com\n[2]: b.png \"B\"\n"
+        );
+
+        // 复用已有定义并接续编号
+        let text =
+            "[x](https://a.com) [y](https://y.com)\n\n[1]: https://a.com\n[3]: https://c.com";
+        let edits = to_reference(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[x][1] [y][4]\n\n[1]: https://a.com\n[3]: https://c.com\n[4]: https://y.com\n"
+        );
+
+        // 未选择时只转换光标所在链接
+        let text = "[a](x.md) [b](y.md)\n";
+        let cursor = Range::new(Position::new(0, 12), Position::new(0, 12));
+        let edits = to_reference(&Rope::from_str(text), cursor).unwrap();
+        assert_eq!(apply_edits(text, &edits), "[a](x.md) [b][1]\n\n[1]: y.md\n");
+
+        // 地址中的实体解码后与已有定义比较
+        let text = "[a](https://a.com?x=1&amp;y=2)\n\n[1]: https://a.com?x=1&amp;y=2\n";
+        let edits = to_reference(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[a][1]\n\n[1]: https://a.com?x=1&amp;y=2\n"
+        );
+
+        // 徽章只转换外层链接
+        let text = "[![CI](https://ci.svg)](https://ci.com)\n";
+        let edits = to_reference(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[![CI](https://ci.svg)][1]\n\n[1]: https://ci.com\n"
+        );
+
+        // 地址相同但标题不同时使用不同定义
+        let text = "[a](x.md \"A\") [b](x.md \"B\") [c](x.md)\n\n[1]: x.md\n  \"B\"\n";
+        let edits = to_reference(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[a][2] [b][1] [c][3]\n\n[1]: x.md\n  \"B\"\n[2]: x.md \"A\"\n[3]: x.md\n"
+        );
+    }
+
+    #[test]
+    fn test_to_inline() {
+        let text =
+            "[a][1] [b][] [c]\n[d][1]\n\n[1]: https://a.com \"A\"\n[b]: b.md\n[C]: <c d.md>\n";
+        let first = Range::new(Position::new(0, 0), Position::new(0, 16));
+        let edits = to_inline(&Rope::from_str(text), first).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[a](https://a.com \"A\") [b](b.md) [c](<c d.md>)\n[d][1]\n\n[1]: https://a.com \"A\"\n"
+        );
+        assert!(to_inline(&Rope::from_str("[a](b)\n"), all("[a](b)\n")).is_none());
+
+        // 徽章只转换外层链接，内层仍在使用的定义保留
+        let text = "[![CI][1]][2]\n\n[1]: https://ci.svg\n[2]: https://ci.com\n";
+        let edits = to_inline(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[![CI][1]](https://ci.com)\n\n[1]: https://ci.svg\n"
+        );
+
+        // 删除定义时包含写在后续行的标题
+        let text = "[a][1]\n\n[1]: https://a.com\n    'A'\nafter\n";
+        let edits = to_inline(&Rope::from_str(text), all(text)).unwrap();
+        assert_eq!(
+            apply_edits(text, &edits),
+            "[a](https://a.com 'A')\n\nafter\n"
+        );
+    }
+
+    #[test]
+    fn test_link_from_clipboard() {
+        let range = Range::new(Position::new(0, 0), Position::new(0, 4));
+        let edit = link_from_clipboard(range, "docs", Some(" https://docs.rs\n")).unwrap();
+        assert_eq!(edit.new_text, "[docs](https://docs.rs)");
+        assert!(link_from_clipboard(range, "docs", Some("not a url")).is_none());
+        assert!(link_from_clipboard(range, "docs", None).is_none());
+    }
+}
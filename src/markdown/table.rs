@@ -1,43 +1,81 @@
+use std::cmp::Ordering;
+
 use async_lsp::lsp_types::{Position, Range, TextEdit};
 use comrak::{
-    Arena, ComrakOptions, ExtensionOptions,
+    Arena,
     nodes::{AstNode, NodeValue, TableAlignment},
     parse_document,
 };
-use ropey::RopeSlice;
+use ropey::{Rope, RopeSlice};
 use unicode_width::UnicodeWidthStr;
 
+use super::{continuation_prefix, get_comrak_options};
+use crate::action_inner::natural_cmp;
+
 /// 格式化 Markdown 表格
 pub fn format(rope: RopeSlice, range: Range) -> Vec<TextEdit> {
     let tables = parse_tables(rope, range.start);
     tables
         .iter()
-        .map(
-            |Table {
-                 header,
-                 alignments,
-                 rows,
-                 range,
-                 col_widths,
-             }| {
-                let separator = gen_separator(alignments, col_widths);
-                let rows: Vec<String> = [header.clone(), separator]
-                    .iter()
-                    .chain(rows.iter())
-                    .map(|row| format_row(row, col_widths, alignments))
-                    .collect();
-
-                let new_text = rows.join("\n");
-
-                TextEdit {
-                    range: *range,
-                    new_text,
-                }
-            },
-        )
+        .map(|table| TextEdit {
+            range: table.range,
+            new_text: render(table),
+        })
         .collect()
 }
 
+/// 格式化文本中的所有表格，保留引用或列表等容器前缀
+///
+/// `in_range` 用于限制处理的行，表格任意一行在范围内即处理整个表格。
+pub(super) fn format_tables(text: &str, in_range: &dyn Fn(usize) -> bool) -> String {
+    let rope = Rope::from_str(text);
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+
+    for table in parse_tables(rope.slice(..), Position::new(0, 0)) {
+        let (start, end) = (
+            table.range.start.line as usize,
+            table.range.end.line as usize,
+        );
+        if !(start..=end).any(in_range) {
+            continue;
+        }
+        let prefix: String = lines[start]
+            .get(..table.range.start.character as usize)
+            .unwrap_or_default()
+            .to_string();
+        let rest = continuation_prefix(&prefix);
+        for (idx, (line, row)) in lines[start..=end]
+            .iter_mut()
+            .zip(render(&table).split('\n'))
+            .enumerate()
+        {
+            let prefix = if idx == 0 { &prefix } else { &rest };
+            *line = format!("{prefix}{row}");
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// 生成对齐后的表格文本
+fn render(table: &Table) -> String {
+    let Table {
+        header,
+        alignments,
+        rows,
+        col_widths,
+        ..
+    } = table;
+    let separator = gen_separator(alignments, col_widths);
+    let rows: Vec<String> = [header.clone(), separator]
+        .iter()
+        .chain(rows.iter())
+        .map(|row| format_row(row, col_widths, alignments))
+        .collect();
+
+    rows.join("\n")
+}
+
 #[derive(Clone, Debug, Default)]
 struct Table {
     header: Vec<String>,
@@ -47,36 +85,216 @@ struct Table {
     range: Range,
 }
 
+impl Table {
+    /// 补齐或截断各行单元格，并重新计算列宽
+    fn normalize(&mut self) {
+        let len = self.alignments.len();
+        for row in std::iter::once(&mut self.header).chain(self.rows.iter_mut()) {
+            row.resize(len, String::new());
+        }
+        self.col_widths = calculate_column_widths(&self.header, &self.rows, &self.alignments);
+    }
+
+    fn insert_column(&mut self, idx: usize) {
+        self.alignments.insert(idx, TableAlignment::None);
+        for row in std::iter::once(&mut self.header).chain(self.rows.iter_mut()) {
+            row.insert(idx.min(row.len()), String::new());
+        }
+    }
+
+    fn remove_column(&mut self, idx: usize) {
+        self.alignments.remove(idx);
+        for row in std::iter::once(&mut self.header).chain(self.rows.iter_mut()) {
+            if idx < row.len() {
+                row.remove(idx);
+            }
+        }
+    }
+
+    fn swap_columns(&mut self, a: usize, b: usize) {
+        self.alignments.swap(a, b);
+        for row in std::iter::once(&mut self.header).chain(self.rows.iter_mut()) {
+            row.swap(a, b);
+        }
+    }
+}
+
+/// 光标所在的表格及单元格，行号为 0 时为表头，1 为分隔线
+fn table_at(doc: &Rope, pos: Position) -> Option<(Table, usize, usize)> {
+    let mut table = parse_tables(doc.slice(..), Position::new(0, 0))
+        .into_iter()
+        .find(|t| t.range.start.line <= pos.line && pos.line <= t.range.end.line)?;
+    table.normalize();
+
+    let line = doc.line(pos.line as usize);
+    let cursor = line.utf16_cu_to_char(pos.character as usize);
+    let line: String = line.chars().collect();
+    // 从表格开始处统计，跳过引用或列表等容器前缀
+    let start = line
+        .get(..table.range.start.character as usize)
+        .map_or(0, |prefix| prefix.chars().count());
+    let trimmed: String = line.chars().skip(start).collect();
+    let leading = usize::from(trimmed.starts_with('|'));
+    let skip = start;
+
+    // 统计光标前未转义的 `|`
+    let mut pipes = 0usize;
+    let mut escaped = false;
+    for c in line.chars().take(cursor).skip(skip) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => pipes += 1,
+            _ => {}
+        }
+    }
+    let column = pipes
+        .saturating_sub(leading)
+        .min(table.alignments.len().saturating_sub(1));
+    let row = (pos.line - table.range.start.line) as usize;
+
+    Some((table, row, column))
+}
+
+/// 单元格排序，均为数值时按数值比较，否则使用自然排序
+fn cell_cmp(a: &str, b: &str) -> Ordering {
+    let number = |s: &str| s.replace(',', "").parse::<f64>().ok();
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => natural_cmp(&a.to_lowercase(), &b.to_lowercase()),
+    }
+}
+
+/// 表格首行之前的容器前缀在续行中的形式
+fn table_prefix(doc: &Rope, table: &Table) -> String {
+    let line = doc.line(table.range.start.line as usize).to_string();
+    continuation_prefix(
+        line.get(..table.range.start.character as usize)
+            .unwrap_or_default(),
+    )
+}
+
+/// 表格的整行替换区域，续行保留容器前缀
+fn table_edit(doc: &Rope, table: &Table) -> TextEdit {
+    let end = table.range.end.line as usize;
+    let line = doc.line(end);
+    let len = line
+        .chars()
+        .collect::<String>()
+        .trim_end_matches(['\n', '\r'])
+        .chars()
+        .count();
+    let range = Range::new(
+        table.range.start,
+        Position::new(end as u32, line.char_to_utf16_cu(len) as u32),
+    );
+    let prefix = table_prefix(doc, table);
+    TextEdit::new(range, render(table).replace('\n', &format!("\n{prefix}")))
+}
+
+/// 光标所在表格的区域、续行容器前缀及单元格，第一行为表头
+pub(super) fn table_cells(doc: &Rope, pos: Position) -> Option<(Range, String, Vec<Vec<String>>)> {
+    let (table, _, _) = table_at(doc, pos)?;
+    let range = table_edit(doc, &table).range;
+    let prefix = table_prefix(doc, &table);
+    let mut rows = vec![table.header];
+    rows.extend(table.rows);
+    Some((range, prefix, rows))
+}
+
+/// 使用单元格生成表格，第一行为表头
+pub(super) fn render_cells(mut rows: Vec<Vec<String>>) -> String {
+    let header = rows.remove(0);
+    let mut table = Table {
+        alignments: vec![TableAlignment::None; header.len()],
+        header,
+        rows,
+        ..Default::default()
+    };
+    table.normalize();
+    render(&table)
+}
+
+/// 光标所在表格的行列编辑
+pub fn edit_actions(doc: &Rope, pos: Position) -> Vec<(&'static str, Vec<TextEdit>)> {
+    let (table, row, column) = match table_at(doc, pos) {
+        Some(found) => found,
+        None => return Vec::new(),
+    };
+    let columns = table.alignments.len();
+    let data_row = row.checked_sub(2);
+
+    let mut edits: Vec<(&'static str, Table)> = Vec::new();
+    let mut with = |title, f: &dyn Fn(&mut Table)| {
+        let mut table = table.clone();
+        f(&mut table);
+        edits.push((title, table));
+    };
+
+    with("Insert Column Left", &|t| t.insert_column(column));
+    with("Insert Column Right", &|t| t.insert_column(column + 1));
+    if columns > 1 {
+        with("Delete Column", &|t| t.remove_column(column));
+    }
+    if column > 0 {
+        with("Move Column Left", &|t| t.swap_columns(column, column - 1));
+    }
+    if column + 1 < columns {
+        with("Move Column Right", &|t| t.swap_columns(column, column + 1));
+    }
+
+    let empty = vec![String::new(); columns];
+    match data_row {
+        Some(idx) => {
+            with("Insert Row Above", &|t| t.rows.insert(idx, empty.clone()));
+            with("Insert Row Below", &|t| {
+                t.rows.insert(idx + 1, empty.clone())
+            });
+            with("Delete Row", &|t| {
+                t.rows.remove(idx);
+            });
+        }
+        None => with("Insert Row Below", &|t| t.rows.insert(0, empty.clone())),
+    }
+
+    for (title, descending) in [
+        ("Sort Table by Column (Ascending)", false),
+        ("Sort Table by Column (Descending)", true),
+    ] {
+        let mut sorted = table.rows.clone();
+        sorted.sort_by(|a, b| {
+            let ord = cell_cmp(&a[column], &b[column]);
+            if descending { ord.reverse() } else { ord }
+        });
+        if sorted != table.rows {
+            with(title, &|t| t.rows = sorted.clone());
+        }
+    }
+
+    for (title, alignment) in [
+        ("Align Column Left", TableAlignment::Left),
+        ("Align Column Center", TableAlignment::Center),
+        ("Align Column Right", TableAlignment::Right),
+        ("Align Column None", TableAlignment::None),
+    ] {
+        if table.alignments[column] != alignment {
+            with(title, &|t| t.alignments[column] = alignment);
+        }
+    }
+
+    edits
+        .into_iter()
+        .map(|(title, mut table)| {
+            table.normalize();
+            (title, vec![table_edit(doc, &table)])
+        })
+        .collect()
+}
+
 /// 解析表格内容
 fn parse_tables(rope: RopeSlice, start_line: Position) -> Vec<Table> {
     let arena = Arena::new();
-    let options = ComrakOptions {
-        extension: ExtensionOptions {
-            table: true,
-            strikethrough: false,
-            tagfilter: false,
-            autolink: false,
-            tasklist: false,
-            superscript: false,
-            header_ids: None,
-            footnotes: false,
-            description_lists: false,
-            front_matter_delimiter: None,
-            multiline_block_quotes: false,
-            alerts: false,
-            math_dollars: false,
-            math_code: false,
-            wikilinks_title_after_pipe: false,
-            wikilinks_title_before_pipe: false,
-            underline: false,
-            subscript: false,
-            spoiler: false,
-            greentext: false,
-            image_url_rewriter: None,
-            link_url_rewriter: None,
-        },
-        ..Default::default()
-    };
+    let options = get_comrak_options();
 
     let root = parse_document(&arena, &rope.to_string(), &options);
 
@@ -263,3 +481,138 @@ fn get_alignment_cell_minimum_width(alignment: &TableAlignment) -> usize {
         TableAlignment::None => 3,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::Position;
+    use ropey::Rope;
+
+    use super::{edit_actions, format_tables, table_at};
+    use crate::markdown::apply_edits;
+
+    const TABLE: &str = "text\n\n| a | b |\n|---|:-:|\n| 1 | 2 |\n| 3 | 4 |\n";
+
+    fn action(pos: Position, title: &str) -> Option<String> {
+        let doc = Rope::from_str(TABLE);
+        edit_actions(&doc, pos)
+            .into_iter()
+            .find(|(t, _)| *t == title)
+            .map(|(_, edits)| apply_edits(TABLE, &edits))
+    }
+
+    #[test]
+    fn test_format_tables() {
+        let text = "> |a|b|\n> |-|-|\n> |1|22|\n\n|x|\n|-|\n";
+        assert_eq!(
+            format_tables(text, &|_| true),
+            "> | a   | b   |\n> | --- | --- |\n> | 1   | 22  |\n\n| x   |\n| --- |\n"
+        );
+        assert_eq!(
+            format_tables(text, &|line| line > 3),
+            "> |a|b|\n> |-|-|\n> |1|22|\n\n| x   |\n| --- |\n"
+        );
+
+        // 列表项中的表格续行使用缩进而不是列表标记
+        assert_eq!(
+            format_tables("- |a|\n  |-|\n", &|_| true),
+            "- | a   |\n  | --- |\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_table_actions() {
+        let text = "> | a | b |\n> |---|---|\n> | 2 | x |\n> | 1 | y |\n";
+        let doc = Rope::from_str(text);
+        let apply = |pos, title| {
+            edit_actions(&doc, pos)
+                .into_iter()
+                .find(|(t, _)| *t == title)
+                .map(|(_, edits)| apply_edits(text, &edits))
+        };
+        assert_eq!(
+            apply(Position::new(0, 4), "Insert Column Left").unwrap(),
+            "> |     | a   | b   |\n> | --- | --- | --- |\n> |     | 2   | x   |\n> |     | 1   | y   |\n"
+        );
+        assert_eq!(
+            apply(Position::new(2, 8), "Sort Table by Column (Descending)").unwrap(),
+            "> | a   | b   |\n> | --- | --- |\n> | 1   | y   |\n> | 2   | x   |\n"
+        );
+        assert_eq!(
+            apply(Position::new(2, 4), "Sort Table by Column (Ascending)").unwrap(),
+            "> | a   | b   |\n> | --- | --- |\n> | 1   | y   |\n> | 2   | x   |\n"
+        );
+    }
+
+    #[test]
+    fn test_table_at() {
+        let doc = Rope::from_str(TABLE);
+        let (_, row, column) = table_at(&doc, Position::new(4, 6)).unwrap();
+        assert_eq!((row, column), (2, 1));
+        let (_, row, column) = table_at(&doc, Position::new(2, 0)).unwrap();
+        assert_eq!((row, column), (0, 0));
+        assert!(table_at(&doc, Position::new(0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_column_actions() {
+        assert_eq!(
+            action(Position::new(4, 6), "Move Column Left").unwrap(),
+            "text\n\n|   b   | a   |\n| :---: | --- |\n|   2   | 1   |\n|   4   | 3   |\n"
+        );
+        assert_eq!(
+            action(Position::new(4, 2), "Insert Column Right").unwrap(),
+            "text\n\n| a   |     |   b   |\n| --- | --- | :---: |\n| 1   |     |   2   |\n| 3   |     |   4   |\n"
+        );
+        assert_eq!(
+            action(Position::new(4, 2), "Delete Column").unwrap(),
+            "text\n\n|   b   |\n| :---: |\n|   2   |\n|   4   |\n"
+        );
+        assert_eq!(
+            action(Position::new(4, 2), "Align Column Right").unwrap(),
+            "text\n\n|    a |   b   |\n| ---: | :---: |\n|    1 |   2   |\n|    3 |   4   |\n"
+        );
+        assert!(action(Position::new(4, 2), "Move Column Left").is_none());
+    }
+
+    #[test]
+    fn test_sort_actions() {
+        let text = "| name | size |\n|---|---:|\n| B2 | 30 |\n| b10 | 1,200 |\n| a | 4.5 |\n";
+        let doc = Rope::from_str(text);
+        let sort = |pos, title| {
+            edit_actions(&doc, pos)
+                .into_iter()
+                .find(|(t, _)| *t == title)
+                .map(|(_, edits)| apply_edits(text, &edits))
+        };
+        let header = "| name |  size |\n| ---- | ----: |\n";
+        let ascending = format!("{header}| a    |   4.5 |\n| B2   |    30 |\n| b10  | 1,200 |\n");
+        let descending = format!("{header}| b10  | 1,200 |\n| B2   |    30 |\n| a    |   4.5 |\n");
+
+        assert_eq!(
+            sort(Position::new(2, 2), "Sort Table by Column (Ascending)").unwrap(),
+            ascending
+        );
+        assert_eq!(
+            sort(Position::new(2, 2), "Sort Table by Column (Descending)").unwrap(),
+            descending
+        );
+        // 按数值而不是字符串排序
+        assert_eq!(
+            sort(Position::new(2, 8), "Sort Table by Column (Descending)").unwrap(),
+            descending
+        );
+    }
+
+    #[test]
+    fn test_row_actions() {
+        assert_eq!(
+            action(Position::new(4, 2), "Delete Row").unwrap(),
+            "text\n\n| a   |   b   |\n| --- | :---: |\n| 3   |   4   |\n"
+        );
+        assert_eq!(
+            action(Position::new(2, 2), "Insert Row Below").unwrap(),
+            "text\n\n| a   |   b   |\n| --- | :---: |\n|     |       |\n| 1   |   2   |\n| 3   |   4   |\n"
+        );
+        assert!(action(Position::new(3, 2), "Delete Row").is_none());
+    }
+}
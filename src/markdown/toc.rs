@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use async_lsp::lsp_types::{Position, Range, TextEdit};
+use comrak::{
+    Arena,
+    nodes::{AstNode, NodeValue},
+    parse_document,
+};
+use ropey::Rope;
+
+use super::get_comrak_options;
+
+const TOC_START: &str = "<!-- toc -->";
+const TOC_STOP: &str = "<!-- tocstop -->";
+
+/// 标题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// GitHub 风格锚点
+    pub slug: String,
+    pub setext: bool,
+    /// 起止行，从 0 开始，setext 标题包含下划线行
+    pub start_line: usize,
+    pub end_line: usize,
+    /// 首行中标题开始的字节列，之前为引用或列表等容器前缀
+    pub column: usize,
+}
+
+/// GitHub 风格锚点生成，重复标题追加 `-1`、`-2` 后缀
+#[derive(Default)]
+pub(super) struct Slugger {
+    counts: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let count = self.counts.entry(base.clone()).or_default();
+        let slug = match *count {
+            0 => base.clone(),
+            n => format!("{base}-{n}"),
+        };
+        *count += 1;
+        slug
+    }
+}
+
+/// 转换为 GitHub 风格锚点：小写、去除标点、空格替换为 `-`
+pub(super) fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 解析文档中的所有标题，代码块中的内容不会被识别
+pub(super) fn headings(text: &str) -> Vec<Heading> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, text, &get_comrak_options());
+
+    let mut slugger = Slugger::default();
+    root.descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            let NodeValue::Heading(heading) = &data.value else {
+                return None;
+            };
+            let text = inline_text(node);
+            Some(Heading {
+                level: heading.level,
+                slug: slugger.slug(&text),
+                text,
+                setext: heading.setext,
+                start_line: data.sourcepos.start.line - 1,
+                end_line: data.sourcepos.end.line - 1,
+                column: data.sourcepos.start.column - 1,
+            })
+        })
+        .collect()
+}
+
+/// 提取节点的纯文本内容
+pub(super) fn inline_text<'a>(node: &'a AstNode<'a>) -> String {
+    node.descendants()
+        .filter_map(|child| match &child.data.borrow().value {
+            NodeValue::Text(text) => Some(text.clone()),
+            NodeValue::Code(code) => Some(code.literal.clone()),
+            NodeValue::SoftBreak | NodeValue::LineBreak => Some(" ".to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 生成或更新 `<!-- toc -->` 与 `<!-- tocstop -->` 之间的目录
+///
+/// 文档中没有标记时在光标所在行插入。
+pub(super) fn toc(
+    doc: &Rope,
+    cursor: Position,
+    min_level: u8,
+    max_level: u8,
+) -> Option<(&'static str, TextEdit)> {
+    let text = doc.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    let marker = |m: &str| lines.iter().position(|line| line.trim() == m);
+
+    let (start, stop) = match (marker(TOC_START), marker(TOC_STOP)) {
+        (Some(start), Some(stop)) if start < stop => (Some(start), stop),
+        _ => (None, 0),
+    };
+
+    let all = headings(&text);
+    let items: Vec<&Heading> = all
+        .iter()
+        .filter(|h| (min_level..=max_level).contains(&h.level))
+        // 目录区域内的内容不计入
+        .filter(|h| start.is_none_or(|start| h.start_line < start || h.start_line > stop))
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+
+    let base = items.iter().map(|h| h.level).min().unwrap_or(min_level);
+    let list: String = items
+        .iter()
+        .map(|h| {
+            let indent = "  ".repeat((h.level - base) as usize);
+            let text = h.text.replace('[', "\\[").replace(']', "\\]");
+            format!("{indent}- [{text}](#{})\n", h.slug)
+        })
+        .collect();
+
+    match start {
+        Some(start) => {
+            let current: String = lines[start + 1..stop]
+                .iter()
+                .map(|line| format!("{line}\n"))
+                .collect();
+            if current == list {
+                return None;
+            }
+            let range = Range::new(
+                Position::new(start as u32 + 1, 0),
+                Position::new(stop as u32, 0),
+            );
+            Some(("Update TOC", TextEdit::new(range, list)))
+        }
+        None => {
+            let pos = Position::new(cursor.line, 0);
+            let new_text = format!("{TOC_START}\n{list}{TOC_STOP}\n");
+            Some((
+                "Generate TOC",
+                TextEdit::new(Range::new(pos, pos), new_text),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::Position;
+    use ropey::Rope;
+
+    use super::{Slugger, headings, slugify, toc};
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("API `v2` & more"), "api-v2--more");
+        assert_eq!(slugify("配置 选项"), "配置-选项");
+        assert_eq!(slugify("snake_case-name"), "snake_case-name");
+
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.slug("Usage"), "usage");
+        assert_eq!(slugger.slug("Usage"), "usage-1");
+        assert_eq!(slugger.slug("Usage"), "usage-2");
+    }
+
+    #[test]
+    fn test_headings() {
+        let text = "# Title\n\n```\n# not heading\n```\n\nSub `code`\n---\n";
+        let headings = headings(text);
+        assert_eq!(headings.len(), 2);
+        assert_eq!(headings[1].text, "Sub code");
+        assert_eq!(headings[1].slug, "sub-code");
+        assert!(headings[1].setext);
+        assert_eq!((headings[1].start_line, headings[1].end_line), (6, 7));
+    }
+
+    #[test]
+    fn test_toc() {
+        let doc = Rope::from_str("# Title\n\n## A\n\n### B\n\n## A\n");
+        let (title, edit) = toc(&doc, Position::new(1, 0), 2, 6).unwrap();
+        assert_eq!(title, "Generate TOC");
+        assert_eq!(
+            edit.new_text,
+            "<!-- toc -->\n- [A](#a)\n  - [B](#b)\n- [A](#a-1)\n<!-- tocstop -->\n"
+        );
+
+        let doc = Rope::from_str("# Title\n<!-- toc -->\n- old\n<!-- tocstop -->\n## New\n");
+        let (title, edit) = toc(&doc, Position::new(0, 0), 1, 6).unwrap();
+        assert_eq!(title, "Update TOC");
+        assert_eq!(edit.range.start, Position::new(2, 0));
+        assert_eq!(edit.range.end, Position::new(3, 0));
+        assert_eq!(edit.new_text, "- [Title](#title)\n  - [New](#new)\n");
+    }
+}
@@ -0,0 +1,329 @@
+use async_lsp::lsp_types::{Position, Range, TextEdit};
+use comrak::{Arena, nodes::NodeValue, parse_document};
+use ropey::Rope;
+use unicode_width::UnicodeWidthStr;
+
+use super::get_comrak_options;
+use crate::encoding::selected_lines;
+
+/// 不能出现在行首的标点
+const NO_BREAK_BEFORE: &str = "，。、；：！？）》」』】〉”’,.;:!?)]}%";
+
+/// 段落重排与合并为单行
+pub(super) fn wrap_actions(
+    doc: &Rope,
+    range: Range,
+    width: usize,
+) -> Vec<(&'static str, Vec<TextEdit>)> {
+    let (start, end) = selected_lines(&range);
+    let paragraphs = paragraphs(doc, start, end);
+    if paragraphs.is_empty() {
+        return Vec::new();
+    }
+
+    let mut items = Vec::new();
+    for (title, width) in [("Wrap Paragraph", Some(width)), ("Unwrap Paragraph", None)] {
+        let edits: Vec<TextEdit> = paragraphs
+            .iter()
+            .filter_map(|p| p.reflow(doc, width))
+            .collect();
+        if !edits.is_empty() {
+            items.push((title, edits));
+        }
+    }
+    items
+}
+
+/// 段落的行范围与首行前缀
+struct Paragraph {
+    start: usize,
+    end: usize,
+    prefix: String,
+}
+
+/// 与所选行相交的段落
+fn paragraphs(doc: &Rope, start: usize, end: usize) -> Vec<Paragraph> {
+    let text = doc.to_string();
+    let arena = Arena::new();
+    let root = parse_document(&arena, &text, &get_comrak_options());
+
+    root.descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            if !matches!(data.value, NodeValue::Paragraph) {
+                return None;
+            }
+            let pos = data.sourcepos;
+            let (first, last) = (pos.start.line - 1, pos.end.line - 1);
+            if first > end || last < start {
+                return None;
+            }
+            let line = doc.line(first).to_string();
+            let prefix = line.get(..pos.start.column - 1)?.to_string();
+            Some(Paragraph {
+                start: first,
+                end: last,
+                prefix,
+            })
+        })
+        .collect()
+}
+
+impl Paragraph {
+    /// 后续行前缀：保留引用标记，其余替换为空格以形成悬挂缩进
+    fn continuation_prefix(&self) -> String {
+        self.prefix
+            .chars()
+            .map(|c| if c == '>' { '>' } else { ' ' })
+            .collect()
+    }
+
+    /// 按宽度重排，`width` 为空时合并为单行，结果不变时返回 `None`
+    fn reflow(&self, doc: &Rope, width: Option<usize>) -> Option<TextEdit> {
+        let quotes = self.prefix.matches('>').count();
+        let lines: Vec<String> = (self.start..=self.end)
+            .map(|idx| {
+                let line = doc.line(idx).to_string();
+                let line = line.trim_end_matches(['\n', '\r']).to_string();
+                match idx == self.start {
+                    true => line[self.prefix.len()..].to_string(),
+                    false => strip_prefix(&line, quotes).to_string(),
+                }
+            })
+            .collect();
+
+        // 以硬换行分段
+        let mut segments: Vec<(Vec<&str>, &str)> = vec![(Vec::new(), "")];
+        for line in &lines {
+            let segment = segments.last_mut().unwrap();
+            if let Some(content) = line.strip_suffix('\\') {
+                segment.0.push(content);
+                segment.1 = "\\";
+                segments.push((Vec::new(), ""));
+            } else if line.ends_with("  ") {
+                segment.0.push(line.trim_end());
+                segment.1 = "  ";
+                segments.push((Vec::new(), ""));
+            } else {
+                segment.0.push(line.trim_end());
+            }
+        }
+
+        let cont = self.continuation_prefix();
+        let mut out: Vec<String> = Vec::new();
+        for (segment, hard_break) in segments.iter().filter(|(s, _)| !s.is_empty()) {
+            let atoms = atoms(segment);
+            let wrapped = match width {
+                Some(width) => fill(&atoms, width.saturating_sub(cont.width())),
+                None => vec![join(&atoms)],
+            };
+            let count = wrapped.len();
+            for (i, line) in wrapped.into_iter().enumerate() {
+                let prefix = if out.is_empty() { &self.prefix } else { &cont };
+                let suffix = if i + 1 == count { *hard_break } else { "" };
+                out.push(format!("{prefix}{line}{suffix}"));
+            }
+        }
+
+        let new_text = out.join("\n");
+        let old_text = (self.start..=self.end)
+            .map(|idx| {
+                doc.line(idx)
+                    .to_string()
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if new_text == old_text {
+            return None;
+        }
+
+        let last = doc.line(self.end);
+        let len = last
+            .to_string()
+            .trim_end_matches(['\n', '\r'])
+            .chars()
+            .count();
+        let range = Range::new(
+            Position::new(self.start as u32, 0),
+            Position::new(self.end as u32, last.char_to_utf16_cu(len) as u32),
+        );
+        Some(TextEdit::new(range, new_text))
+    }
+}
+
+/// 去除后续行的缩进与引用标记
+fn strip_prefix(line: &str, quotes: usize) -> &str {
+    let mut rest = line.trim_start();
+    for _ in 0..quotes {
+        match rest.strip_prefix('>') {
+            Some(r) => rest = r.trim_start(),
+            None => break,
+        }
+    }
+    rest
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF | 0x2E80..=0x9FFF | 0xA960..=0xA97F | 0xAC00..=0xD7FF
+        | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFFEF | 0x20000..=0x3FFFF)
+}
+
+/// 最小排版单位及其前是否有空格；CJK 字符各自成为一个单位
+fn atoms(lines: &[&str]) -> Vec<(String, bool)> {
+    let mut atoms: Vec<(String, bool)> = Vec::new();
+    let mut space = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        // 换行相当于空格，CJK 字符之间除外
+        if i > 0 {
+            let prev_cjk = atoms
+                .last()
+                .and_then(|(a, _)| a.chars().last())
+                .is_some_and(is_cjk);
+            let next_cjk = line.trim_start().chars().next().is_some_and(is_cjk);
+            space = !(prev_cjk && next_cjk);
+        }
+        let mut word = String::new();
+        for c in line.trim().chars() {
+            if c.is_whitespace() {
+                if !word.is_empty() {
+                    atoms.push((std::mem::take(&mut word), space));
+                }
+                space = true;
+            } else if is_cjk(c) {
+                if !word.is_empty() {
+                    atoms.push((std::mem::take(&mut word), space));
+                    space = false;
+                }
+                atoms.push((c.to_string(), space));
+                space = false;
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            atoms.push((word, space));
+        }
+        space = false;
+    }
+
+    if let Some(first) = atoms.first_mut() {
+        first.1 = false;
+    }
+    atoms
+}
+
+fn join(atoms: &[(String, bool)]) -> String {
+    atoms.iter().fold(String::new(), |mut out, (atom, space)| {
+        if *space {
+            out.push(' ');
+        }
+        out.push_str(atom);
+        out
+    })
+}
+
+/// 行首不能出现会改变 Markdown 结构的内容
+fn breakable_before(atom: &str) -> bool {
+    let first = atom.chars().next().unwrap_or_default();
+    if NO_BREAK_BEFORE.contains(first) && atom.chars().count() == 1 {
+        return false;
+    }
+    let ordered = atom.trim_end_matches(['.', ')']);
+    !(matches!(atom, "-" | "*" | "+" | "=" | "|")
+        || atom.starts_with('#')
+        || atom.starts_with('>')
+        || (ordered.len() < atom.len() && ordered.chars().all(|c| c.is_ascii_digit())))
+}
+
+/// 贪心填充，单个超长的单位独占一行
+fn fill(atoms: &[(String, bool)], width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for (atom, space) in atoms {
+        let sep = if *space && !line.is_empty() { " " } else { "" };
+        let fits = line.width() + sep.len() + atom.width() <= width;
+        if line.is_empty() || fits || !breakable_before(atom) {
+            line.push_str(sep);
+            line.push_str(atom);
+        } else {
+            lines.push(std::mem::take(&mut line));
+            line.push_str(atom);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::{atoms, fill, wrap_actions};
+    use crate::markdown::apply_edits;
+
+    fn action(text: &str, line: u32, title: &str, width: usize) -> Option<String> {
+        let range = Range::new(Position::new(line, 0), Position::new(line, 1));
+        wrap_actions(&Rope::from_str(text), range, width)
+            .into_iter()
+            .find(|(t, _)| *t == title)
+            .map(|(_, edits)| apply_edits(text, &edits))
+    }
+
+    #[test]
+    fn test_fill_cjk() {
+        let atoms = atoms(&["使用 Rust 编写，支持中文。"]);
+        assert_eq!(fill(&atoms, 10), ["使用 Rust", "编写，支持", "中文。"]);
+        assert_eq!(atoms.len(), 11);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let text = "- one two three four five six\n  seven\n\nnext\n";
+        assert_eq!(
+            action(text, 0, "Wrap Paragraph", 16).unwrap(),
+            "- one two three\n  four five six\n  seven\n\nnext\n"
+        );
+        assert_eq!(
+            action(text, 1, "Unwrap Paragraph", 16).unwrap(),
+            "- one two three four five six seven\n\nnext\n"
+        );
+
+        let text = "> 第一行\n> 第二行 and more  \n> after break\n";
+        assert_eq!(
+            action(text, 0, "Unwrap Paragraph", 80).unwrap(),
+            "> 第一行第二行 and more  \n> after break\n"
+        );
+        assert!(action("short\n", 0, "Wrap Paragraph", 80).is_none());
+
+        // 选择结束于下一段落行首时不包含该段落
+        let range = Range::new(Position::new(0, 0), Position::new(2, 0));
+        let doc = Rope::from_str("aaaa bbbb\n\ncccc dddd\n");
+        let edits = wrap_actions(&doc, range, 4)
+            .into_iter()
+            .find(|(t, _)| *t == "Wrap Paragraph")
+            .map(|(_, edits)| edits)
+            .unwrap();
+        assert_eq!(
+            apply_edits(&doc.to_string(), &edits),
+            "aaaa\nbbbb\n\ncccc dddd\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_keeps_structure() {
+        // 避免在行首生成列表标记
+        let text = "aaaa bbbb - cc\n";
+        assert_eq!(
+            action(text, 0, "Wrap Paragraph", 9).unwrap(),
+            "aaaa bbbb -\ncc\n"
+        );
+    }
+}
@@ -48,6 +48,12 @@ pub enum StrOrSeq {
     Array(Vec<String>),
 }
 
+impl Default for StrOrSeq {
+    fn default() -> Self {
+        StrOrSeq::String(String::new())
+    }
+}
+
 impl std::fmt::Display for StrOrSeq {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -0,0 +1,125 @@
+// 内嵌脚本 action
+//
+// 使用 Rhai 在进程内执行 action，不依赖 shell，在各平台表现一致。
+//
+// 脚本可用的变量：
+// - `text`: 文档全文
+// - `selection`: 选中内容
+// - `range`: 选择区域 `#{ start: #{ line, character }, end: #{ line, character } }`
+// - `vars`: 所有 snippet 变量，如 `vars.TM_FILENAME`
+//
+// 脚本返回值：
+// - 字符串：替换选中内容
+// - `#{ range, newText }` 或其数组：返回 `TextEdit`
+// - `()`：不做修改
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use async_lsp::lsp_types::{Range, TextEdit};
+use rhai::{Dynamic, Engine, Scope};
+
+/// 单个脚本最大执行步数，避免死循环卡住编辑器
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// 脚本上下文
+#[derive(Debug, Default)]
+pub struct ScriptContext {
+    pub text: String,
+    pub selection: String,
+    pub range: Range,
+    pub variables: HashMap<String, String>,
+}
+
+/// 执行脚本并转换为编辑内容
+pub fn run(script: &str, ctx: ScriptContext) -> Result<Vec<TextEdit>> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let mut scope = Scope::new();
+    scope.push_constant("text", ctx.text);
+    scope.push_constant("selection", ctx.selection);
+    scope.push_constant("range", to_dynamic(ctx.range)?);
+    scope.push_constant("vars", to_dynamic(ctx.variables)?);
+
+    let value = engine
+        .eval_with_scope::<Dynamic>(&mut scope, script)
+        .map_err(|e| anyhow!("script error: {e}"))?;
+
+    to_edits(value, ctx.range)
+}
+
+fn to_edits(value: Dynamic, range: Range) -> Result<Vec<TextEdit>> {
+    if value.is_unit() {
+        return Ok(Vec::new());
+    }
+
+    if value.is_string() {
+        let new_text = value.into_string().map_err(|t| anyhow!("invalid {t}"))?;
+        return Ok(vec![TextEdit { range, new_text }]);
+    }
+
+    if value.is_map() {
+        return Ok(vec![from_dynamic(&value)?]);
+    }
+
+    if value.is_array() {
+        return from_dynamic(&value);
+    }
+
+    Err(anyhow!("unsupported script result: {}", value.type_name()))
+}
+
+fn to_dynamic<T: serde::Serialize>(value: T) -> Result<Dynamic> {
+    rhai::serde::to_dynamic(value).map_err(|e| anyhow!("{e}"))
+}
+
+fn from_dynamic<T: serde::de::DeserializeOwned>(value: &Dynamic) -> Result<T> {
+    rhai::serde::from_dynamic(value).map_err(|e| anyhow!("invalid script result: {e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use async_lsp::lsp_types::{Position, Range};
+
+    use super::{ScriptContext, run};
+
+    fn context() -> ScriptContext {
+        ScriptContext {
+            text: "hello world".to_owned(),
+            selection: "world".to_owned(),
+            range: Range::new(Position::new(0, 6), Position::new(0, 11)),
+            variables: [("TM_FILENAME".to_owned(), "a.md".to_owned())].into(),
+        }
+    }
+
+    #[test]
+    fn test_return_string() {
+        let edits = run("selection.to_upper() + \" \" + vars.TM_FILENAME", context()).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "WORLD a.md");
+        assert_eq!(edits[0].range, context().range);
+    }
+
+    #[test]
+    fn test_return_edits() {
+        let script = r#"
+            [
+                #{ range: #{ start: #{ line: 0, character: 0 }, end: #{ line: 0, character: 5 } }, newText: "hi" },
+                #{ range: range, newText: text.len().to_string() },
+            ]
+        "#;
+        let edits = run(script, context()).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].new_text, "hi");
+        assert_eq!(edits[0].range.end.character, 5);
+        assert_eq!(edits[1].new_text, "11");
+    }
+
+    #[test]
+    fn test_return_unit_and_error() {
+        assert!(run("let a = 1;", context()).unwrap().is_empty());
+        assert!(run("loop {}", context()).is_err());
+        assert!(run("42", context()).is_err());
+    }
+}
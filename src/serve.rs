@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ops::ControlFlow, time::Duration};
+use std::{collections::HashMap, ops::ControlFlow, path::PathBuf, time::Duration};
 
 use async_lsp::{
     ClientSocket, ErrorCode, LanguageServer, ResponseError,
@@ -8,12 +8,18 @@ use async_lsp::{
         CodeAction, CodeActionKind, CodeActionOptions, CodeActionParams,
         CodeActionProviderCapability, CodeActionResponse, ColorInformation,
         ColorProviderCapability, CompletionOptions, CompletionParams, CompletionResponse,
-        DidChangeConfigurationParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-        DidOpenTextDocumentParams, DidSaveTextDocumentParams, DocumentColorParams,
-        ExecuteCommandOptions, ExecuteCommandParams, InitializeParams, InitializeResult,
-        PositionEncodingKind, SaveOptions, ServerCapabilities, ServerInfo,
+        Diagnostic, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+        DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+        DocumentColorParams, DocumentFormattingParams, DocumentRangeFormattingParams,
+        DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandOptions, ExecuteCommandParams,
+        FoldingRange, FoldingRangeParams, FoldingRangeProviderCapability, InitializeParams,
+        InitializeResult, MessageActionItem, MessageType, OneOf, PositionEncodingKind,
+        PublishDiagnosticsParams, Range, SaveOptions, ServerCapabilities, ServerInfo,
+        ShowDocumentParams, ShowMessageParams, ShowMessageRequestParams,
         TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
-        TextDocumentSyncSaveOptions, TextEdit, WorkDoneProgressOptions, WorkspaceEdit,
+        TextDocumentSyncSaveOptions, TextEdit, Url, WorkDoneProgressOptions, WorkspaceEdit,
+        notification::{PublishDiagnostics, ShowMessage},
+        request::{ShowDocument, ShowMessageRequest},
     },
     panic::CatchUnwindLayer,
     router::Router,
@@ -24,20 +30,37 @@ use copypasta::{ClipboardContext, ClipboardProvider};
 use futures::future::BoxFuture;
 use ropey::Rope;
 use tower::ServiceBuilder;
-use tracing::{Level, info};
+use tracing::{Level, error, info};
 
 use crate::{
-    action::{Actions, shell},
-    action_inner::case_actions,
+    action::{ActionData, Actions, actions_list_clear, shell_logged},
+    action_inner::{
+        case_actions, codec_actions, comment_actions, data_actions, line_actions, number_actions,
+        quote_actions, rename_actions,
+    },
+    audit,
     colors::extract_colors,
     config::LspConfig,
     encoding::{get_current_word, get_range_content, is_field},
+    errors::Error,
     markdown,
     snippet::Snippets,
     state::State,
+    trust::{TrustStatus, TrustStore},
     variables::VariableInit,
 };
 
+#[cfg(feature = "script")]
+use crate::{
+    script::{self, ScriptContext},
+    variables::Variables,
+};
+
+const SHOW_ACTION_LOG: &str = "hx-lsp.showActionLog";
+
+const TRUST_ACTION: &str = "Trust";
+const DENY_ACTION: &str = "Don't Trust";
+
 static EMPTY_ROPE: std::sync::OnceLock<Rope> = std::sync::OnceLock::new();
 
 /// LSP 服务器
@@ -63,6 +86,155 @@ impl Server {
         ControlFlow::Continue(())
     }
 
+    /// 项目 actions 是否可信
+    ///
+    /// 首次出现或内容变更时通过 `window/showMessageRequest` 询问用户，
+    /// 在得到答复前不加载项目 actions。
+    fn project_actions_trusted(&self) -> bool {
+        if !self.state.config.project_actions {
+            return false;
+        }
+
+        let root = self.state.root.clone();
+        let hash = match self.state.actions_hash() {
+            Some(hash) => hash,
+            None => return false,
+        };
+
+        if let Some(status) = self.state.get_trust(&hash) {
+            return status == TrustStatus::Trusted;
+        }
+
+        // 无法读取信任记录时视为不可信
+        let status = match TrustStore::open() {
+            Some(store) => store.status(&root, &hash),
+            None => TrustStatus::Denied,
+        };
+        self.state.set_trust(hash.clone(), status);
+        if status != TrustStatus::Unknown {
+            return status == TrustStatus::Trusted;
+        }
+
+        let client = self.client.clone();
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            let params = ShowMessageRequestParams {
+                typ: MessageType::WARNING,
+                message: format!(
+                    "hx-lsp: {} has project actions which run shell commands. Trust this workspace?",
+                    root.display()
+                ),
+                actions: Some(
+                    [TRUST_ACTION, DENY_ACTION]
+                        .iter()
+                        .map(|title| MessageActionItem {
+                            title: title.to_string(),
+                            properties: HashMap::new(),
+                        })
+                        .collect(),
+                ),
+            };
+
+            let trusted = match client.request::<ShowMessageRequest>(params).await {
+                Ok(Some(item)) => item.title == TRUST_ACTION,
+                Ok(None) => return,
+                Err(err) => {
+                    error!("trust request fail: {err:?}");
+                    return;
+                }
+            };
+
+            let status = match trusted {
+                true => TrustStatus::Trusted,
+                false => TrustStatus::Denied,
+            };
+            state.set_trust(hash.clone(), status);
+
+            if let Some(mut store) = TrustStore::open()
+                && let Err(err) = store.set(&root, &hash, trusted)
+            {
+                error!("trust store save fail: {err:?}");
+            }
+            if trusted {
+                actions_list_clear();
+            }
+        });
+
+        false
+    }
+
+    /// 根据选择区域构建变量上下文
+    fn variable_init(
+        uri: &Url,
+        lang_id: &str,
+        doc: &Rope,
+        range: &Range,
+        root: PathBuf,
+    ) -> Option<VariableInit> {
+        // 当前行
+        let line = doc.get_line(range.end.line as usize)?;
+        // 当前 word
+        let cursor_word = get_current_word(&line, range.end.character as usize).unwrap_or_default();
+        // 当前 选择区域
+        let range_content = get_range_content(doc, range).map(|s| s.to_string());
+
+        Some(VariableInit {
+            file_path: uri.to_file_path().unwrap_or_default(),
+            work_path: root,
+            language_id: lang_id.to_owned(),
+            line_pos: range.start.line as usize,
+            cursor_pos: range.end.character as usize,
+            line_text: line.to_string(),
+            current_word: cursor_word.to_string(),
+            selected_text: range_content.unwrap_or_default(),
+            clipboard: Self::get_clipboard_content(),
+        })
+    }
+
+    /// 通过 `window/showMessage` 提示用户
+    fn show_message(&self, typ: MessageType, message: String) {
+        if let Err(err) = self
+            .client
+            .notify::<ShowMessage>(ShowMessageParams { typ, message })
+        {
+            error!("show message fail: {err:?}");
+        }
+    }
+
+    /// 通过 `textDocument/publishDiagnostics` 发布文档诊断
+    fn publish_diagnostics(&self, uri: &Url) {
+        let lang_id = self.state.get_language_id(uri);
+        if lang_id != "markdown" {
+            return;
+        }
+        let doc = self.state.get_document(uri);
+        let Some(diagnostics) = markdown::diagnostics(
+            &lang_id,
+            uri,
+            &doc,
+            &self.state.documents(),
+            &self.state.root,
+            &self.state.config,
+        ) else {
+            return;
+        };
+        self.send_diagnostics(uri, diagnostics);
+    }
+
+    /// 发布诊断列表，空列表用于清除客户端已有诊断
+    fn send_diagnostics(&self, uri: &Url, diagnostics: Vec<Diagnostic>) {
+        if let Err(err) = self
+            .client
+            .notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+                uri.clone(),
+                diagnostics,
+                None,
+            ))
+        {
+            error!("publish diagnostics fail: {err:?}");
+        }
+    }
+
     fn get_clipboard_content() -> Option<String> {
         ClipboardContext::new().ok()?.get_contents().ok()
     }
@@ -152,6 +324,9 @@ impl LanguageServer for Server {
             self.state.set_config(config);
         }
 
+        // 格式化能力需显式开启，避免抢占其他语言服务器的格式化
+        let formatting = self.state.config.formatting;
+
         Box::pin(async move {
             Ok(InitializeResult {
                 capabilities: ServerCapabilities {
@@ -170,9 +345,14 @@ impl LanguageServer for Server {
                     )),
                     completion_provider: Some(CompletionOptions {
                         resolve_provider: Some(false),
+                        trigger_characters: Some(["(", "/", "#", "["].map(String::from).to_vec()),
                         ..Default::default()
                     }),
                     color_provider: Some(ColorProviderCapability::Simple(true)),
+                    document_formatting_provider: formatting.then_some(OneOf::Left(true)),
+                    document_range_formatting_provider: formatting.then_some(OneOf::Left(true)),
+                    document_symbol_provider: Some(OneOf::Left(true)),
+                    folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
                     text_document_sync: Some(TextDocumentSyncCapability::Options(
                         TextDocumentSyncOptions {
                             open_close: Some(true),
@@ -185,7 +365,12 @@ impl LanguageServer for Server {
                         },
                     )),
                     execute_command_provider: Some(ExecuteCommandOptions {
-                        commands: vec!["reload snippets".to_string(), "reload actions".to_string()],
+                        commands: vec![
+                            "reload snippets".to_string(),
+                            "reload actions".to_string(),
+                            SHOW_ACTION_LOG.to_string(),
+                            markdown::COPY_AS_CSV.to_string(),
+                        ],
                         work_done_progress_options: WorkDoneProgressOptions {
                             work_done_progress: Some(true),
                         },
@@ -207,10 +392,15 @@ impl LanguageServer for Server {
         let config = LspConfig::from_json(&params.settings);
         self.state.set_config(config);
         tracing::info!(
-            "Configuration updated: markdown={}, documentColor={}",
+            "Configuration updated: markdown={}, documentColor={}, projectActions={}",
             self.state.config.markdown,
-            self.state.config.document_color
+            self.state.config.document_color,
+            self.state.config.project_actions
         );
+        // 按新配置重新发布，关闭 markdown 时清除已有诊断
+        for uri in self.state.documents().keys() {
+            self.publish_diagnostics(uri);
+        }
         ControlFlow::Continue(())
     }
 
@@ -221,6 +411,7 @@ impl LanguageServer for Server {
 
         self.state
             .on_document_open(&uri, content, Some(language_id));
+        self.publish_diagnostics(&uri);
 
         ControlFlow::Continue(())
     }
@@ -238,11 +429,15 @@ impl LanguageServer for Server {
         let uri = params.text_document.uri;
         let content = params.text.map(Rope::from).unwrap_or_default();
         self.state.on_document_save(&uri, content);
+        self.publish_diagnostics(&uri);
         ControlFlow::Continue(())
     }
 
     fn did_close(&mut self, params: DidCloseTextDocumentParams) -> Self::NotifyResult {
         let uri = params.text_document.uri;
+        if self.state.get_language_id(&uri) == "markdown" {
+            self.send_diagnostics(&uri, Vec::new());
+        }
         self.state.clean(&uri);
         ControlFlow::Continue(())
     }
@@ -277,6 +472,22 @@ impl LanguageServer for Server {
             None => return Box::pin(async move { Ok(None) }),
         };
 
+        // 先判断语言，避免为其他语言复制所有已打开文档
+        if lang_id == "markdown"
+            && !markdown_disabled
+            && let Some(items) = markdown::completion(
+                &lang_id,
+                &uri,
+                &doc,
+                pos,
+                &self.state.documents(),
+                &root,
+                &self.state.config,
+            )
+        {
+            return Box::pin(async move { Ok(Some(CompletionResponse::Array(items))) });
+        }
+
         if is_field(&line, pos.character as usize) {
             return Box::pin(async move { Ok(None) });
         }
@@ -296,6 +507,7 @@ impl LanguageServer for Server {
         let variable_init = VariableInit {
             file_path: uri.to_file_path().unwrap_or_default(),
             work_path: root.clone(),
+            language_id: lang_id.clone(),
             line_pos: params.text_document_position.position.line as usize,
             cursor_pos: pos.character as usize,
             line_text: line.to_string(),
@@ -322,39 +534,49 @@ impl LanguageServer for Server {
         let lang_id = state.get_language_id(&uri);
         let root = state.root.clone();
 
-        // 当前行
-        let line = match doc.get_line(params.range.end.line as usize) {
-            Some(line) => line,
+        let variable_init = match Self::variable_init(&uri, &lang_id, &doc, &params.range, root) {
+            Some(init) => init,
             None => return Box::pin(async move { Ok(None) }),
         };
-        // 当前 word
-        let cursor_word =
-            get_current_word(&line, params.range.end.character as usize).unwrap_or_default();
         // 当前 选择区域
         let range_content = get_range_content(&doc, &params.range);
-        let range_content_str = range_content.as_ref().map(|s| s.to_string());
 
-        let clipboard_content = Self::get_clipboard_content();
+        let project = self.project_actions_trusted();
+        let actions = Actions::get_lang(lang_id.clone(), &variable_init, project);
 
-        let variable_init = VariableInit {
-            file_path: uri.to_file_path().unwrap_or_default(),
-            work_path: root.clone(),
-            line_pos: params.range.start.line as usize,
-            cursor_pos: params.range.end.character as usize,
-            line_text: line.to_string(),
-            current_word: cursor_word.to_string(),
-            selected_text: range_content_str.unwrap_or_default(),
-            clipboard: clipboard_content,
-        };
-
-        let actions = Actions::get_lang(lang_id.clone(), &variable_init);
+        let data_items = data_actions(&lang_id, &doc, &params);
+        let footnote_items =
+            markdown::footnote_actions(&lang_id, &doc, &params, &self.state.config);
 
         let markdown_actions = if self.state.config.markdown {
-            markdown::actions(lang_id, &doc, &params)
+            markdown::actions(
+                lang_id.clone(),
+                &doc,
+                &params,
+                &self.state.config,
+                variable_init.clipboard.as_deref(),
+            )
         } else {
             Vec::new()
         };
 
+        let open_documents = self
+            .state
+            .config
+            .case_all_documents
+            .then(|| self.state.documents());
+
+        let rename_items = rename_actions(
+            &doc,
+            &params,
+            &self.state.config.case_actions,
+            open_documents.as_ref(),
+        );
+
+        let range_content = *range_content
+            .as_ref()
+            .unwrap_or(&EMPTY_ROPE.get_or_init(Rope::new).slice(..));
+
         let actions = actions
             .to_code_action_items(&variable_init, &params.clone().into())
             .iter()
@@ -362,13 +584,28 @@ impl LanguageServer for Server {
                 self.state.set_action(action.title.clone(), data.clone());
                 action.clone().into()
             })
-            .chain(case_actions(
-                *range_content
-                    .as_ref()
-                    .unwrap_or(&EMPTY_ROPE.get_or_init(Rope::new).slice(..)),
-                &params,
-            ))
+            .chain(data_items.into_iter().map(|(action, reformat)| {
+                let data = ActionData::from(params.clone()).with_reformat(reformat);
+                self.state.set_action(action.title.clone(), data);
+                action.into()
+            }))
+            .chain(case_actions(&doc, &params, &self.state.config.case_actions))
+            .chain(rename_items.into_iter().map(|(action, rename)| {
+                let data = ActionData::from(params.clone()).with_rename(rename);
+                self.state.set_action(action.title.clone(), data);
+                action.into()
+            }))
+            .chain(line_actions(range_content, &params))
+            .chain(codec_actions(range_content, &params))
+            .chain(number_actions(range_content, &params))
+            .chain(quote_actions(&doc, &params))
+            .chain(comment_actions(&lang_id, &doc, &params))
             .chain(markdown_actions)
+            .chain(footnote_items.into_iter().map(|(action, footnote)| {
+                let data = ActionData::from(params.clone()).with_footnote(footnote);
+                self.state.set_action(action.title.clone(), data);
+                action.into()
+            }))
             .collect();
 
         Box::pin(async move { Ok(Some(actions)) })
@@ -379,7 +616,7 @@ impl LanguageServer for Server {
         mut params: CodeAction,
     ) -> BoxFuture<'static, Result<CodeAction, ResponseError>> {
         let title = params.title.clone();
-        let data = if let Some(data) = self.state.get_action(title) {
+        let data = if let Some(data) = self.state.get_action(title.clone()) {
             data
         } else {
             return Box::pin(async move { Ok(params) });
@@ -401,9 +638,83 @@ impl LanguageServer for Server {
 
         let resolved_action = &mut params;
 
+        if let Some(rename) = data.rename {
+            let doc = self.state.get_document(&uri);
+            let changes = match rename.all_documents {
+                true => rename.apply(&self.state.documents()),
+                false => rename.apply([(&uri, &doc)]),
+            };
+            resolved_action.edit = Some(WorkspaceEdit::new(changes));
+
+            return Box::pin(async move { Ok(params) });
+        }
+
+        if let Some(footnote) = data.footnote {
+            let doc = self.state.get_document(&uri);
+            match footnote.apply(&doc) {
+                Some(edits) => {
+                    let mut changes = HashMap::new();
+                    changes.insert(uri.clone(), edits);
+                    resolved_action.edit = Some(WorkspaceEdit::new(changes));
+                }
+                None => self.show_message(MessageType::INFO, format!("{title}: nothing to change")),
+            }
+
+            return Box::pin(async move { Ok(params) });
+        }
+
+        if let Some(reformat) = data.reformat {
+            let doc = self.state.get_document(&uri);
+            let indent = match self.state.config.insert_spaces {
+                true => " ".repeat(self.state.config.tab_size),
+                false => "\t".to_owned(),
+            };
+            match reformat.apply(&doc, &indent) {
+                Ok(edit) => {
+                    let mut changes = HashMap::new();
+                    changes.insert(uri.clone(), vec![edit]);
+                    resolved_action.edit = Some(WorkspaceEdit::new(changes));
+                }
+                Err(err) => self.show_message(MessageType::ERROR, format!("{title}: {err}")),
+            }
+
+            return Box::pin(async move { Ok(params) });
+        }
+
+        #[cfg(feature = "script")]
+        if let Some(script) = data.script {
+            let doc = self.state.get_document(&uri);
+            let lang_id = self.state.get_language_id(&uri);
+            let root = self.state.root.clone();
+            let ctx = match Self::variable_init(&uri, &lang_id, &doc, &range, root) {
+                Some(init) => ScriptContext {
+                    text: doc.to_string(),
+                    selection: init.selected_text.clone(),
+                    range,
+                    variables: Variables::resolve_all(&init),
+                },
+                None => return Box::pin(async move { Ok(params) }),
+            };
+
+            match script::run(&script, ctx) {
+                Ok(edits) if !edits.is_empty() => {
+                    resolved_action.data = None;
+                    let mut changes = HashMap::new();
+                    changes.insert(uri.clone(), edits);
+                    resolved_action.edit = Some(WorkspaceEdit::new(changes));
+                    resolved_action.kind = Some(CodeActionKind::REFACTOR_REWRITE);
+                }
+                Ok(_) => {}
+                Err(err) => self.show_message(MessageType::ERROR, format!("{title}: {err}")),
+            }
+
+            return Box::pin(async move { Ok(params) });
+        }
+
+        let file_path = uri.to_file_path().unwrap_or_default();
         if let Some(output) = data
             .command
-            .and_then(|cmd| shell(&cmd, &selected).ok())
+            .and_then(|cmd| shell_logged(&title, &file_path, &cmd, &selected).ok())
             .filter(|o| !o.is_empty())
         {
             resolved_action.data = None;
@@ -444,10 +755,105 @@ impl LanguageServer for Server {
         Box::pin(async move { Ok(colors) })
     }
 
+    fn formatting(
+        &mut self,
+        params: DocumentFormattingParams,
+    ) -> BoxFuture<'static, Result<Option<Vec<TextEdit>>, ResponseError>> {
+        let uri = params.text_document.uri;
+        let doc = self.state.get_document(&uri);
+        let lang_id = self.state.get_language_id(&uri);
+
+        let edits = markdown::format(&lang_id, &doc, None, &self.state.config);
+
+        Box::pin(async move { Ok(Some(edits)) })
+    }
+
+    fn range_formatting(
+        &mut self,
+        params: DocumentRangeFormattingParams,
+    ) -> BoxFuture<'static, Result<Option<Vec<TextEdit>>, ResponseError>> {
+        let uri = params.text_document.uri;
+        let doc = self.state.get_document(&uri);
+        let lang_id = self.state.get_language_id(&uri);
+
+        let edits = markdown::format(&lang_id, &doc, Some(params.range), &self.state.config);
+
+        Box::pin(async move { Ok(Some(edits)) })
+    }
+
+    fn document_symbol(
+        &mut self,
+        params: DocumentSymbolParams,
+    ) -> BoxFuture<'static, Result<Option<DocumentSymbolResponse>, ResponseError>> {
+        let uri = params.text_document.uri;
+        let doc = self.state.get_document(&uri);
+        let lang_id = self.state.get_language_id(&uri);
+
+        let symbols = markdown::document_symbols(&lang_id, &doc, &self.state.config);
+
+        Box::pin(async move { Ok(Some(DocumentSymbolResponse::Nested(symbols))) })
+    }
+
+    fn folding_range(
+        &mut self,
+        params: FoldingRangeParams,
+    ) -> BoxFuture<'static, Result<Option<Vec<FoldingRange>>, ResponseError>> {
+        let uri = params.text_document.uri;
+        let doc = self.state.get_document(&uri);
+        let lang_id = self.state.get_language_id(&uri);
+
+        let ranges = markdown::folding_ranges(&lang_id, &doc, &self.state.config);
+
+        Box::pin(async move { Ok(Some(ranges)) })
+    }
+
     fn execute_command(
         &mut self,
         params: ExecuteCommandParams,
     ) -> BoxFuture<'static, Result<Option<serde_json::Value>, ResponseError>> {
+        if params.command == SHOW_ACTION_LOG {
+            let client = self.client.clone();
+            return Box::pin(async move {
+                let path = audit::log_path();
+                let uri = match Url::from_file_path(&path) {
+                    Ok(uri) if path.exists() => uri,
+                    _ => {
+                        return Err(ResponseError::new(
+                            ErrorCode::INVALID_REQUEST,
+                            Error::NotFound(path.display().to_string()).to_string(),
+                        ));
+                    }
+                };
+
+                client
+                    .request::<ShowDocument>(ShowDocumentParams {
+                        uri,
+                        external: Some(false),
+                        take_focus: Some(true),
+                        selection: None,
+                    })
+                    .await
+                    .map_err(|e| ResponseError::new(ErrorCode::INTERNAL_ERROR, e.to_string()))?;
+
+                Ok(None)
+            });
+        }
+
+        if params.command == markdown::COPY_AS_CSV {
+            let content = params.arguments.first().and_then(|arg| arg.as_str());
+            let result = match content {
+                Some(content) => ClipboardContext::new()
+                    .and_then(|mut ctx| ctx.set_contents(content.to_owned()))
+                    .map_err(|e| e.to_string()),
+                None => Err("missing CSV content".to_string()),
+            };
+            return Box::pin(async move {
+                result
+                    .map(|_| None)
+                    .map_err(|e| ResponseError::new(ErrorCode::INTERNAL_ERROR, e))
+            });
+        }
+
         if let Err(e) = self.state.execute_command(&params.command) {
             return Box::pin(async move {
                 Err(ResponseError::new(
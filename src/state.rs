@@ -11,9 +11,11 @@ use tracing::debug;
 
 use crate::{
     action::{ActionData, actions_list_clear},
+    audit,
     config::LspConfig,
     encoding::{OffsetEncoding, lsp_pos_to_pos},
     snippet::snippets_list_clear,
+    trust::{TrustStatus, is_project_action, project_actions_hash},
 };
 
 #[derive(Default, Clone)]
@@ -26,6 +28,8 @@ pub struct State {
     language_ids: Arc<RwLock<HashMap<Url, String>>>,
     color_cache: Arc<RwLock<HashMap<Url, CachedColors>>>,
     action_cache: Arc<RwLock<HashMap<String, ActionData>>>,
+    trust_cache: Arc<RwLock<HashMap<String, TrustStatus>>>,
+    actions_hash: Arc<RwLock<Option<Option<String>>>>,
 }
 
 #[derive(Default, Clone)]
@@ -92,6 +96,11 @@ impl State {
             .unwrap_or_default()
     }
 
+    /// 所有已打开的文档
+    pub fn documents(&self) -> HashMap<Url, Rope> {
+        self.documents.read().expect("Get Documents Fail").clone()
+    }
+
     pub fn get_language_id(&self, uri: &Url) -> String {
         self.language_ids
             .read()
@@ -138,6 +147,11 @@ impl State {
             // 内容变更时清除缓存
             self.clear_color(uri);
         }
+        if let Ok(path) = uri.to_file_path()
+            && is_project_action(&self.root, &path)
+        {
+            self.clear_actions_hash();
+        }
     }
 
     /// 变更内容
@@ -214,6 +228,47 @@ impl State {
             .clear();
     }
 
+    /// 项目 actions 哈希对应的信任状态
+    pub fn get_trust(&self, hash: &str) -> Option<TrustStatus> {
+        self.trust_cache
+            .read()
+            .expect("Failed to read trust cache")
+            .get(hash)
+            .copied()
+    }
+
+    pub fn set_trust(&self, hash: String, status: TrustStatus) {
+        self.trust_cache
+            .write()
+            .expect("Failed to write trust cache")
+            .insert(hash, status);
+    }
+
+    /// 项目 actions 内容哈希，保存或重新加载 actions 前复用缓存
+    pub fn actions_hash(&self) -> Option<String> {
+        if let Some(hash) = self
+            .actions_hash
+            .read()
+            .expect("Failed to read actions hash")
+            .clone()
+        {
+            return hash;
+        }
+        let hash = project_actions_hash(&self.root);
+        *self
+            .actions_hash
+            .write()
+            .expect("Failed to write actions hash") = Some(hash.clone());
+        hash
+    }
+
+    pub fn clear_actions_hash(&self) {
+        *self
+            .actions_hash
+            .write()
+            .expect("Failed to write actions hash") = None;
+    }
+
     /// 获取或更新颜色缓存
     pub fn get_color(&self, uri: &Url) -> Option<Vec<ColorInformation>> {
         let content_hash = self.get_hash(uri).unwrap_or_default();
@@ -258,6 +313,7 @@ impl State {
     }
 
     pub fn set_config(&mut self, config: LspConfig) {
+        audit::set_enabled(config.action_log);
         self.config = config;
     }
 
@@ -265,6 +321,7 @@ impl State {
         match command {
             "reload actions" => {
                 actions_list_clear();
+                self.clear_actions_hash();
                 Ok(())
             }
             "reload snippets" => {
@@ -0,0 +1,185 @@
+// 工作区信任
+//
+// 项目目录下的 `.helix/actions/*.json` 会在代码操作请求时执行 shell filter，
+// 因此首次出现或内容变更时需要用户确认后才会加载。
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::error;
+
+use crate::loader::{Dirs, data_dir};
+
+/// 工作区信任状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustStatus {
+    Trusted,
+    Denied,
+    Unknown,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct TrustEntry {
+    hash: String,
+    trusted: bool,
+}
+
+/// 信任记录，保存在数据目录下的 `trust.json`
+#[derive(Debug, Default)]
+pub struct TrustStore {
+    path: PathBuf,
+    entries: HashMap<String, TrustEntry>,
+}
+
+impl TrustStore {
+    /// 读取默认位置的信任记录，无法确定数据目录时返回 `None`
+    pub fn open() -> Option<TrustStore> {
+        let dir = data_dir();
+        if !dir.is_absolute() {
+            error!("trust store unavailable: data dir not found");
+            return None;
+        }
+        Some(TrustStore::load(dir.join("trust.json")))
+    }
+
+    pub fn load(path: PathBuf) -> TrustStore {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| match serde_json::from_str(&s) {
+                Ok(entries) => Some(entries),
+                Err(err) => {
+                    error!("trust store parse fail: {err:?}");
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        TrustStore { path, entries }
+    }
+
+    /// 查询工作区状态，内容哈希变更后视为未知
+    pub fn status(&self, root: &Path, hash: &str) -> TrustStatus {
+        match self.entries.get(&workspace_key(root)) {
+            Some(entry) if entry.hash == hash => match entry.trusted {
+                true => TrustStatus::Trusted,
+                false => TrustStatus::Denied,
+            },
+            _ => TrustStatus::Unknown,
+        }
+    }
+
+    /// 记录用户选择并写入文件
+    pub fn set(&mut self, root: &Path, hash: &str, trusted: bool) -> Result<()> {
+        self.entries.insert(
+            workspace_key(root),
+            TrustEntry {
+                hash: hash.to_owned(),
+                trusted,
+            },
+        );
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+fn workspace_key(root: &Path) -> String {
+    root.to_string_lossy().into_owned()
+}
+
+fn project_actions_dir(root: &Path) -> PathBuf {
+    root.join(".helix").join(Dirs::Actions.to_string())
+}
+
+/// 是否为项目 actions 文件
+pub fn is_project_action(root: &Path, path: &Path) -> bool {
+    path.parent() == Some(project_actions_dir(root).as_path())
+        && path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// 计算项目 actions 文件的内容哈希，没有项目 actions 时返回 `None`
+pub fn project_actions_hash(root: &Path) -> Option<String> {
+    let dir = project_actions_dir(root);
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            (path.extension()? == "json").then_some(path)
+        })
+        .collect();
+
+    if files.is_empty() {
+        return None;
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.file_name()?.as_encoded_bytes());
+        hasher.update(fs::read(&file).unwrap_or_default());
+    }
+
+    Some(
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use super::{TrustStatus, TrustStore, is_project_action, project_actions_hash};
+
+    #[test]
+    fn test_trust_store() {
+        let dir = std::env::temp_dir().join(format!("hx-lsp-trust-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("trust.json");
+        let root = Path::new("/tmp/project");
+
+        let mut store = TrustStore::load(path.clone());
+        assert_eq!(store.status(root, "a"), TrustStatus::Unknown);
+
+        store.set(root, "a", true).unwrap();
+        let store = TrustStore::load(path.clone());
+        assert_eq!(store.status(root, "a"), TrustStatus::Trusted);
+        assert_eq!(store.status(root, "b"), TrustStatus::Unknown);
+
+        let mut store = TrustStore::load(path);
+        store.set(root, "b", false).unwrap();
+        assert_eq!(store.status(root, "b"), TrustStatus::Denied);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_project_actions_hash() {
+        let root = std::env::temp_dir().join(format!("hx-lsp-actions-{}", uuid::Uuid::new_v4()));
+        assert_eq!(project_actions_hash(&root), None);
+
+        let dir = root.join(".helix").join("actions");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("go.json"), "{}").unwrap();
+        assert!(is_project_action(&root, &dir.join("go.json")));
+        assert!(!is_project_action(&root, &root.join("go.json")));
+        let hash = project_actions_hash(&root);
+        assert!(hash.is_some());
+
+        fs::write(dir.join("go.json"), r#"{"a": {}}"#).unwrap();
+        assert_ne!(project_actions_hash(&root), hash);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
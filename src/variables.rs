@@ -14,7 +14,10 @@ use time::{
 };
 use uuid::Uuid;
 
-use crate::encoding::char_is_word;
+use crate::{
+    comment::{self, CommentTokens},
+    encoding::char_is_word,
+};
 
 pub fn init() {
     init_time_offset();
@@ -26,6 +29,7 @@ pub fn init() {
 pub struct VariableInit {
     pub file_path: PathBuf,
     pub work_path: PathBuf,
+    pub language_id: String,
     pub line_text: String,
     pub current_word: String,
     pub selected_text: String,
@@ -213,13 +217,29 @@ impl Variables {
             Self::RandomHex => random_hex(6),
             Self::Uuid => Uuid::new_v4().to_string(),
 
-            // 注释（需要语言上下文）
-            Self::BlockCommentStart => self.to_string(), // 示例值，需根据语言调整
-            Self::BlockCommentEnd => self.to_string(),
-            Self::LineComment => self.to_string(),
+            // 注释，语言没有对应符号时为空
+            Self::BlockCommentStart => comment_tokens(init)
+                .and_then(|t| t.block)
+                .map(|(start, _)| start.to_string())
+                .unwrap_or_default(),
+            Self::BlockCommentEnd => comment_tokens(init)
+                .and_then(|t| t.block)
+                .map(|(_, end)| end.to_string())
+                .unwrap_or_default(),
+            Self::LineComment => comment_tokens(init)
+                .and_then(|t| t.line)
+                .map(str::to_string)
+                .unwrap_or_default(),
         }
     }
 
+    /// 解析所有变量，供脚本 action 使用
+    pub fn resolve_all(init: &VariableInit) -> HashMap<String, String> {
+        Self::all()
+            .map(|var| (var.to_string(), var.resolve(init)))
+            .collect()
+    }
+
     /// 批量替换文本中的变量
     pub fn replace_all(text: &str, init: &VariableInit) -> String {
         let automaton = init_variable_automaton();
@@ -263,7 +283,7 @@ fn build_replaced_string(
 }
 
 /// 初始化时区偏移
-fn init_time_offset() -> &'static UtcOffset {
+pub(crate) fn init_time_offset() -> &'static UtcOffset {
     // 时区偏移缓存
     // time local offset not support multi-thread
     static TIME_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
@@ -316,6 +336,10 @@ fn convert_to_owned(items: Vec<BorrowedFormatItem>) -> Vec<OwnedFormatItem> {
     items.iter().map(|item| item.into()).collect()
 }
 
+fn comment_tokens(init: &VariableInit) -> Option<CommentTokens> {
+    comment::tokens(&init.language_id)
+}
+
 /// 获取当前时间（带缓存时区）
 fn current_time() -> OffsetDateTime {
     OffsetDateTime::now_utc().to_offset(*init_time_offset())